@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use futures::stream::{FuturesUnordered, StreamExt};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use rand::distributions::Alphanumeric;
+use rand::{thread_rng, Rng};
+use reqwest::Url;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_util::sync::CancellationToken;
+use crate::RDClient;
+use crate::data_struct::RDError;
+
+const PAGE_SUCCESS: &str = "<html><body>Authorization done, you can close this window.</body></html>";
+const PAGE_FAILED: &str = "<html><body>Authorization failed, you can close this window.</body></html>";
+/// Hosts of redirect uri listen accepts, so listener is not reachable from network.
+const LOOPBACK_HOSTS: &[&str] = &["127.0.0.1", "[::1]", "localhost"];
+/// Connections sending no request in time are closed.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// OAuth2 authorization code flow for applications with their own client id and client secret.
+/// Open authorization_url in a browser then wait code with listen or give it to exchange_code.
+#[derive(Debug)]
+pub struct AuthorizationCodeFlow {
+    client: RDClient,
+    client_secret: String,
+    redirect_uri: String,
+    state: String,
+}
+
+impl AuthorizationCodeFlow {
+
+    pub(crate) fn new(client: RDClient, client_secret: String, redirect_uri: String) -> AuthorizationCodeFlow {
+        let state = thread_rng().sample_iter(Alphanumeric).take(32).map(char::from).collect();
+        AuthorizationCodeFlow { client, client_secret, redirect_uri, state }
+    }
+
+    /// Url where user authorize application.
+    pub fn authorization_url(&self) -> String {
+        format!("{}auth?client_id={}&redirect_uri={}&response_type=code&state={}",
+                self.client.oauth_url,
+                utf8_percent_encode(&self.client.client_id, NON_ALPHANUMERIC),
+                utf8_percent_encode(&self.redirect_uri, NON_ALPHANUMERIC),
+                self.state)
+    }
+
+    /// Random value sent back with code on redirect uri.
+    pub fn state(&self) -> &str {
+        &self.state
+    }
+
+    pub fn redirect_uri(&self) -> &str {
+        &self.redirect_uri
+    }
+
+    /// Exchange code received on redirect uri for a token.
+    pub async fn exchange_code(self, code: &str) -> Result<RDClient, RDError> {
+        let mut params = HashMap::new();
+        params.insert("client_id", self.client.client_id.clone());
+        params.insert("client_secret", self.client_secret.clone());
+        params.insert("code", code.to_string());
+        params.insert("redirect_uri", self.redirect_uri.clone());
+        params.insert("grant_type", "authorization_code".to_string());
+
+        let token = self.client.request_token(&params).await?;
+        self.client.start_session(self.client.client_id.clone(), self.client_secret, token).await?;
+
+        Ok(self.client)
+    }
+
+    /// Listen on redirect uri (must be a loopback address with port) until browser is redirected there.
+    pub async fn listen(self, cancel: &CancellationToken) -> Result<RDClient, RDError> {
+        let redirect = Url::parse(&self.redirect_uri).map_err(|_| RDError::BAD_REDIRECT_URI)?;
+        let host = redirect.host_str().filter(|host| LOOPBACK_HOSTS.contains(host)).ok_or(RDError::BAD_REDIRECT_URI)?;
+        let port = redirect.port_or_known_default().ok_or(RDError::BAD_REDIRECT_URI)?;
+
+        let listener = TcpListener::bind((host.trim_matches(|c| c == '[' || c == ']'), port)).await?;
+
+        // Connections are read at once, so an idle connection does not block the redirect.
+        let mut connections = FuturesUnordered::new();
+        let code = loop {
+            tokio::select! {
+                _ = cancel.cancelled() => return Err(RDError::AUTH_CANCELLED),
+                accepted = listener.accept() => {
+                    if let Ok((stream, _)) = accepted {
+                        connections.push(tokio::time::timeout(READ_TIMEOUT, self.receive(stream, redirect.path())));
+                    }
+                },
+                Some(received) = connections.next(), if !connections.is_empty() => match received {
+                    Ok(Some(Ok(code))) => break code,
+                    Ok(Some(Err(e))) => return Err(e),
+                    // Not the redirect, malformed, broken or idle connection.
+                    Ok(None) | Err(_) => {},
+                },
+            }
+        };
+        drop(connections);
+        self.exchange_code(code.as_str()).await
+    }
+
+    /// Read one http request.
+    /// None when request is not the redirect with state of flow or connection failed.
+    async fn receive(&self, mut stream: TcpStream, path: &str) -> Option<Result<String, RDError>> {
+        let mut buffer = Vec::new();
+        let mut chunk = [0u8; 1024];
+        while !buffer.windows(4).any(|w| w == b"\r\n\r\n") && buffer.len() < 8192 {
+            let read = stream.read(&mut chunk).await.ok()?;
+            if read == 0 {
+                break;
+            }
+            buffer.extend_from_slice(&chunk[..read]);
+        }
+
+        let request = String::from_utf8_lossy(&buffer);
+        let mut request_line = request.lines().next()?.split_whitespace();
+        let target = match (request_line.next(), request_line.next(), request_line.next()) {
+            (Some(_), Some(target), Some(_)) if target.starts_with('/') => target,
+            _ => {
+                let _ = Self::respond(&mut stream, "400 Bad Request", PAGE_FAILED).await;
+                return None;
+            },
+        };
+        let Ok(url) = Url::parse(format!("http://localhost{}", target).as_str()) else {
+            let _ = Self::respond(&mut stream, "400 Bad Request", PAGE_FAILED).await;
+            return None;
+        };
+        if url.path() != path {
+            let _ = Self::respond(&mut stream, "404 Not Found", "").await;
+            return None;
+        }
+
+        let query = url.query_pairs().into_owned().collect::<HashMap<String, String>>();
+        if query.get("state") != Some(&self.state) {
+            let _ = Self::respond(&mut stream, "400 Bad Request", PAGE_FAILED).await;
+            return None;
+        }
+        // User denied access when code is missing.
+        let result = query.get("code").cloned().ok_or(RDError::AUTH_FAILED);
+
+        let _ = match result {
+            Ok(_) => Self::respond(&mut stream, "200 OK", PAGE_SUCCESS).await,
+            Err(_) => Self::respond(&mut stream, "400 Bad Request", PAGE_FAILED).await,
+        };
+        Some(result)
+    }
+
+    async fn respond(stream: &mut TcpStream, status: &str, body: &str) -> Result<(), RDError> {
+        let response = format!("HTTP/1.1 {}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", status, body.len(), body);
+        stream.write_all(response.as_bytes()).await?;
+        stream.shutdown().await?;
+        Ok(())
+    }
+
+}
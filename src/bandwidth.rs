@@ -0,0 +1,123 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Token bucket over downloaded bytes, burst up to one second of rate.
+/// Clones share the same bucket, so rate can be changed while downloads run.
+#[derive(Debug, Clone)]
+pub struct BandwidthLimiter {
+    bucket: Arc<Mutex<ByteBucket>>,
+}
+
+#[derive(Debug)]
+struct ByteBucket {
+    /// Bytes per second, None for no limit.
+    rate: Option<u64>,
+    /// Negative when bytes were taken in advance.
+    tokens: f64,
+    last: Instant,
+}
+
+impl BandwidthLimiter {
+
+    /// Allow bytes_per_second.
+    pub fn new(bytes_per_second: u64) -> BandwidthLimiter {
+        BandwidthLimiter::with_rate(Some(bytes_per_second))
+    }
+
+    /// Limiter without limit until a rate is set.
+    pub fn unlimited() -> BandwidthLimiter {
+        BandwidthLimiter::with_rate(None)
+    }
+
+    /// Limiter with optional rate.
+    pub fn with_rate(bytes_per_second: Option<u64>) -> BandwidthLimiter {
+        let rate = bytes_per_second.map(|rate| rate.max(1));
+        let tokens = rate.unwrap_or(0) as f64;
+        BandwidthLimiter { bucket: Arc::new(Mutex::new(ByteBucket { rate, tokens, last: Instant::now() })) }
+    }
+
+    /// Change rate, None to remove limit.
+    pub fn set_rate(&self, bytes_per_second: Option<u64>) {
+        let mut bucket = self.bucket.lock().unwrap_or_else(|e| e.into_inner());
+        bucket.fill();
+        bucket.rate = bytes_per_second.map(|rate| rate.max(1));
+        if let Some(rate) = bucket.rate {
+            bucket.tokens = bucket.tokens.min(rate as f64);
+        }
+    }
+
+    pub fn rate(&self) -> Option<u64> {
+        self.bucket.lock().unwrap_or_else(|e| e.into_inner()).rate
+    }
+
+    /// Wait until bytes may be used.
+    /// Bytes are taken at once, next calls wait for them when they exceed rate.
+    pub async fn acquire(&self, bytes: u64) {
+        let wait = {
+            let mut bucket = self.bucket.lock().unwrap_or_else(|e| e.into_inner());
+            bucket.fill();
+            let Some(rate) = bucket.rate else {
+                return;
+            };
+            bucket.tokens -= bytes as f64;
+            if bucket.tokens >= 0.0 {
+                return;
+            }
+            Duration::from_secs_f64(-bucket.tokens / rate as f64)
+        };
+        tokio::time::sleep(wait).await;
+    }
+
+}
+
+impl ByteBucket {
+
+    fn fill(&mut self) {
+        let now = Instant::now();
+        if let Some(rate) = self.rate {
+            self.tokens = (self.tokens + now.duration_since(self.last).as_secs_f64() * rate as f64).min(rate as f64);
+        }
+        self.last = now;
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn bandwidth_refill_cap() {
+        let limiter = BandwidthLimiter::new(1000);
+        let start = Instant::now();
+        limiter.acquire(1000).await;
+        assert_eq!(start.elapsed(), Duration::ZERO);
+        // Bytes over burst wait for refill.
+        limiter.acquire(500).await;
+        assert_eq!(start.elapsed(), Duration::from_millis(500));
+
+        // Idle time never fills more than one second of rate.
+        tokio::time::advance(Duration::from_secs(10)).await;
+        let start = Instant::now();
+        limiter.acquire(1000).await;
+        assert_eq!(start.elapsed(), Duration::ZERO);
+        limiter.acquire(1000).await;
+        assert_eq!(start.elapsed(), Duration::from_secs(1));
+
+        // Lower rate caps tokens left.
+        tokio::time::advance(Duration::from_secs(1)).await;
+        limiter.set_rate(Some(100));
+        let start = Instant::now();
+        limiter.acquire(200).await;
+        assert_eq!(start.elapsed(), Duration::from_secs(1));
+
+        limiter.set_rate(None);
+        let start = Instant::now();
+        limiter.acquire(u64::MAX).await;
+        assert_eq!(start.elapsed(), Duration::ZERO);
+        assert_eq!(BandwidthLimiter::unlimited().rate(), None);
+        assert_eq!(BandwidthLimiter::new(0).rate(), Some(1));
+    }
+
+}
@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use futures::stream::{self, StreamExt};
+use getset::Getters;
+use reqwest::Url;
+use crate::{RDClient, RDTraitAsync};
+use crate::data_struct::RDError;
+use crate::data_struct::unrestrict::Unrestrict;
+use crate::host_matcher::{HostMatcher, HostPatterns, LinkKind};
+
+/// Options of unrestrict_many.
+#[derive(Debug, Clone)]
+pub struct UnrestrictOptions {
+    concurrency: usize,
+    expand_folders: bool,
+    passwords: HashMap<String, String>,
+    remote: Option<bool>,
+    matcher: Option<HostMatcher>,
+}
+
+impl Default for UnrestrictOptions {
+    fn default() -> Self {
+        UnrestrictOptions { concurrency: 4, expand_folders: true, passwords: HashMap::new(), remote: None, matcher: None }
+    }
+}
+
+impl UnrestrictOptions {
+
+    /// 4 links at once, folder links expanded.
+    pub fn new() -> UnrestrictOptions {
+        UnrestrictOptions::default()
+    }
+
+    /// Requests sent at once.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Replace folder links by their links with unrestrict_folder.
+    pub fn expand_folders(mut self, expand_folders: bool) -> Self {
+        self.expand_folders = expand_folders;
+        self
+    }
+
+    /// Password sent for links of host and its subdomains.
+    pub fn password(mut self, host: impl Into<String>, password: impl Into<String>) -> Self {
+        self.passwords.insert(host.into().to_ascii_lowercase(), password.into());
+        self
+    }
+
+    /// Use remote traffic.
+    pub fn remote(mut self, remote: bool) -> Self {
+        self.remote = Some(remote);
+        self
+    }
+
+    /// Matcher used to find folder links, a new one is created by each call otherwise.
+    pub fn host_matcher(mut self, matcher: HostMatcher) -> Self {
+        self.matcher = Some(matcher);
+        self
+    }
+
+    fn password_for(&self, link: &str) -> Option<String> {
+        let url = Url::parse(link).ok()?;
+        let host = url.host_str()?.to_ascii_lowercase();
+        self.passwords.iter()
+            .filter(|(domain, _)| host == **domain || host.ends_with(format!(".{}", domain).as_str()))
+            .max_by_key(|(domain, _)| domain.len())
+            .map(|(_, password)| password.clone())
+    }
+
+}
+
+/// Result of one link of unrestrict_many.
+#[derive(Debug, Getters)]
+pub struct UnrestrictResult {
+    #[getset(get = "pub")]
+    link: String,
+    /// Folder link given in input when link come from a folder.
+    #[getset(get = "pub")]
+    folder: Option<String>,
+    #[getset(get = "pub")]
+    result: Result<Unrestrict, RDError>,
+}
+
+impl UnrestrictResult {
+
+    pub fn into_result(self) -> Result<Unrestrict, RDError> {
+        self.result
+    }
+
+}
+
+/// Path segments of folder links of most hosts, used when host patterns can not be loaded.
+const FOLDER_SEGMENTS: &[&str] = &["folder", "folders", "dir", "directory"];
+
+/// How folder links are found.
+#[derive(Clone)]
+enum FolderCheck {
+    Off,
+    Patterns(Arc<HostPatterns>),
+    /// Host patterns failed to load.
+    Shape,
+}
+
+/// Link to unrestrict or failed folder expansion.
+enum Job {
+    Link(String, Option<String>),
+    Failed(String, RDError),
+}
+
+impl RDClient {
+
+    /// Unrestrict links with bounded concurrency.
+    /// Results keep order of links, links of a folder take place of folder link.
+    /// A failing link does not stop others.
+    /// When host patterns can not be loaded, links with a folder, folders, dir or directory path segment are expanded.
+    pub async fn unrestrict_many(&self, links: Vec<String>, options: UnrestrictOptions) -> Vec<UnrestrictResult> {
+        let check = if options.expand_folders {
+            let matcher = options.matcher.clone().unwrap_or_else(|| self.host_matcher());
+            match matcher.patterns().await {
+                Ok(patterns) => FolderCheck::Patterns(patterns),
+                Err(_) => FolderCheck::Shape,
+            }
+        }
+        else {
+            FolderCheck::Off
+        };
+
+        let jobs = stream::iter(links)
+            .map(|link| self.expand(link, check.clone()))
+            .buffered(options.concurrency)
+            .collect::<Vec<_>>()
+            .await;
+
+        stream::iter(jobs.into_iter().flatten())
+            .map(|job| async {
+                match job {
+                    Job::Link(link, folder) => {
+                        let result = self.unrestrict_link(link.clone(), options.password_for(&link), options.remote).await;
+                        UnrestrictResult { link, folder, result }
+                    },
+                    Job::Failed(link, e) => UnrestrictResult { link, folder: None, result: Err(e) },
+                }
+            })
+            .buffered(options.concurrency)
+            .collect()
+            .await
+    }
+
+    async fn expand(&self, link: String, check: FolderCheck) -> Vec<Job> {
+        let folder = match check {
+            FolderCheck::Off => false,
+            FolderCheck::Patterns(patterns) => matches!(patterns.classify(&link), LinkKind::FOLDER(_)),
+            FolderCheck::Shape => looks_like_folder(&link),
+        };
+        if !folder {
+            return vec![Job::Link(link, None)];
+        }
+
+        match self.unrestrict_folder(link.clone()).await {
+            Ok(links) => links.into_iter().map(|d| Job::Link(d, Some(link.clone()))).collect(),
+            Err(e) => vec![Job::Failed(link, e)],
+        }
+    }
+
+}
+
+/// Check if a path segment of link names a folder.
+fn looks_like_folder(link: &str) -> bool {
+    Url::parse(link.trim()).ok()
+        .and_then(|url| url.path_segments().map(|segments| segments.map(str::to_ascii_lowercase).collect::<Vec<_>>()))
+        .is_some_and(|segments| segments.iter().any(|d| FOLDER_SEGMENTS.contains(&d.as_str())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folder_shape() {
+        assert!(looks_like_folder("https://mega.nz/folder/abc#key"));
+        assert!(looks_like_folder("https://1fichier.com/dir/abc"));
+        assert!(looks_like_folder("https://drive.google.com/drive/Folders/abc"));
+        assert!(!looks_like_folder("https://1fichier.com/?abc"));
+        assert!(!looks_like_folder("https://host.com/file/folder.zip"));
+        assert!(!looks_like_folder("not a link"));
+    }
+
+}
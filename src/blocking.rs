@@ -0,0 +1,319 @@
+use std::sync::Arc;
+use tokio::runtime::{Builder, Runtime};
+use crate::{RDClient, RDTrait, RDTraitAsync};
+use crate::builder::RDClientBuilder;
+use crate::data_struct::download::{Downloads, ParamsDownload};
+use crate::data_struct::{RDError, RDOk};
+use crate::data_struct::container::ParamsContainer;
+use crate::data_struct::host::Hosts;
+use crate::data_struct::settings::{ParamsSetting, Settings};
+use crate::data_struct::streaming::{MediaInfo, ParamsStreaming, StreamingTranscode};
+use crate::data_struct::torrent::{ParamsTorrent, ParamsTorrentFile, ParamsTorrentHost, Torrent, TorrentAdd, TorrentCount, TorrentHost, Torrents};
+use crate::data_struct::traffic::{Traffics, TrafficsPeriod};
+use crate::data_struct::unrestrict::{Unrestrict, UnrestrictCheck};
+use crate::data_struct::user::User;
+
+/// Blocking version of RDClient.
+/// Each call run the async client on an internal runtime, so it must not be used inside an async context.
+#[derive(Debug, Clone)]
+pub struct RDBlockingClient {
+    client: RDClient,
+    runtime: Arc<Runtime>,
+}
+
+impl RDBlockingClient {
+
+    /// Create new RDBlockingClient with api key.
+    pub fn new(api_key: String) -> Result<RDBlockingClient, RDError> {
+        RDBlockingClient::from_client(RDClient::new(api_key))
+    }
+
+    /// Create RDBlockingClient from a builder.
+    pub fn from_builder(builder: RDClientBuilder) -> Result<RDBlockingClient, RDError> {
+        RDBlockingClient::from_client(builder.build()?)
+    }
+
+    /// Wrap an async client.
+    pub fn from_client(client: RDClient) -> Result<RDBlockingClient, RDError> {
+        Ok(RDBlockingClient { client, runtime: Arc::new(Self::runtime()?) })
+    }
+
+    /// Async client used by this client.
+    pub fn client(&self) -> &RDClient {
+        &self.client
+    }
+
+    pub fn client_mut(&mut self) -> &mut RDClient {
+        &mut self.client
+    }
+
+    fn runtime() -> Result<Runtime, RDError> {
+        Ok(Builder::new_current_thread().enable_all().build()?)
+    }
+
+}
+
+pub trait RDTraitBlocking {
+    fn auth() -> Result<RDBlockingClient, RDError> ;
+
+    fn refresh_token(&self) -> Result<RDOk, RDError> ;
+
+    fn get_time(&self) -> Result<String, RDError> ;
+
+    fn get_time_iso(&self) -> Result<String, RDError> ;
+
+    fn disable_access_token(&self) -> Result<(), RDError> ;
+
+    fn get_user(&self) -> Result<User, RDError> ;
+
+    fn get_settings(&self) -> Result<Settings, RDError> ;
+
+    fn update_setting(&self, setting: ParamsSetting) -> Result<(), RDError> ;
+
+    fn convert_points(&self) -> Result<(), RDError> ;
+
+    fn change_password(&self) -> Result<(), RDError> ;
+
+    fn upload_avatar(&self, path: String) -> Result<(), RDError> ;
+
+    fn delete_avatar(&self) -> Result<RDOk, RDError> ;
+
+    fn check_unrestrict(&self, link: String, hoster_password: Option<String>) -> Result<UnrestrictCheck, RDError> ;
+
+    fn unrestrict_link(&self, link: String, hoster_password: Option<String>, remote: Option<bool>) -> Result<Unrestrict, RDError> ;
+
+    fn unrestrict_folder(&self, link: String) -> Result<Vec<String>, RDError> ;
+
+    fn unrestrict_decrypt_special_folder(&self, container: ParamsContainer) -> Result<Vec<String>, RDError> ;
+
+    fn unrestrict_decrypt_folder(&self, link: String) -> Result<Vec<String>, RDError> ;
+
+    fn get_traffic(&self) -> Result<Traffics, RDError> ;
+
+    fn get_traffic_details(&self, start: Option<String> , end: Option<String> ) -> Result<TrafficsPeriod, RDError> ;
+
+    fn get_streaming_transcode(&self, streaming: ParamsStreaming) -> Result<StreamingTranscode, RDError> ;
+
+    fn get_streaming_media_info(&self, streaming: ParamsStreaming) -> Result<MediaInfo, RDError> ;
+
+    fn get_downloads(&self, offset: Option<u32>, page: Option<u32>, limit: Option<u32>) -> Result<Downloads, RDError> ;
+
+    fn remove_download(&self, download: ParamsDownload) -> Result<RDOk, RDError> ;
+
+    fn get_torrents(&self, offset: Option<u32>, page: Option<u32>, limit: Option<u32>, filter: Option<String>) -> Result<Torrents, RDError> ;
+
+    fn get_torrents_info(&self, torrent: ParamsTorrent) -> Result<Torrent, RDError> ;
+
+    fn get_torrents_active_count(&self) -> Result<TorrentCount, RDError> ;
+
+    fn get_torrents_available_hosts(&self) -> Result<Vec<TorrentHost>, RDError> ;
+
+    fn add_torrent_file(&self, path: String, host: Option<ParamsTorrentHost>) -> Result<TorrentAdd, RDError> ;
+
+    fn add_torrent_magnet(&self, magnet: impl Into<String>, host: Option<ParamsTorrentHost>) -> Result<TorrentAdd, RDError> ;
+
+    fn select_torrent_file(&self, torrent: ParamsTorrent, files: ParamsTorrentFile) -> Result<(),RDError> ;
+
+    fn remove_torrent(&self, torrent: ParamsTorrent) -> Result<RDOk, RDError> ;
+
+    fn get_host(&self) -> Result<Hosts, RDError> ;
+
+    fn get_host_regex(&self) -> Result<Vec<String>, RDError> ;
+
+    fn get_host_regex_folder(&self) -> Result<Vec<String>, RDError> ;
+
+    fn get_host_domains(&self) -> Result<Vec<String>, RDError> ;
+
+    fn get_host_with_status(&self) -> Result<Hosts, RDError> ;
+
+}
+
+impl RDTraitBlocking for RDBlockingClient {
+
+    /// Create new RDBlockingClient with oauth2.
+    fn auth() -> Result<RDBlockingClient, RDError> {
+        let runtime = Self::runtime()?;
+        let client = runtime.block_on(RDClient::auth())?;
+        Ok(RDBlockingClient { client, runtime: Arc::new(runtime) })
+    }
+
+    /// Refresh RDClient when use oauth2.
+    /// Token is shared with all clones of this client.
+    fn refresh_token(&self) -> Result<RDOk, RDError> {
+        self.runtime.block_on(self.client.refresh_token())
+    }
+
+    /// Get server time.
+    fn get_time(&self) -> Result<String, RDError> {
+        self.runtime.block_on(self.client.get_time())
+    }
+
+    /// Get server time in ISO.
+    fn get_time_iso(&self) -> Result<String, RDError> {
+        self.runtime.block_on(self.client.get_time_iso())
+    }
+
+    /// Disable current access token
+    fn disable_access_token(&self) -> Result<(), RDError> {
+        self.runtime.block_on(self.client.disable_access_token())
+    }
+
+    /// Get current user info.
+    fn get_user(&self) -> Result<User, RDError> {
+        self.runtime.block_on(self.client.get_user())
+    }
+
+    /// Get current user settings.
+    fn get_settings(&self) -> Result<Settings, RDError> {
+        self.runtime.block_on(self.client.get_settings())
+    }
+
+    /// Update a user setting.
+    fn update_setting(&self, setting: ParamsSetting) -> Result<(), RDError> {
+        self.runtime.block_on(self.client.update_setting(setting))
+    }
+
+    /// Convert fidelity points.
+    fn convert_points(&self) -> Result<(), RDError> {
+        self.runtime.block_on(self.client.convert_points())
+    }
+
+    /// Send the verification email to change the password.
+    fn change_password(&self) -> Result<(), RDError> {
+        self.runtime.block_on(self.client.change_password())
+    }
+
+    /// Upload a new user avatar image.
+    fn upload_avatar(&self, path: String) -> Result<(), RDError> {
+        self.runtime.block_on(self.client.upload_avatar(path))
+    }
+
+    /// Reset user avatar image to default.
+    fn delete_avatar(&self) -> Result<RDOk, RDError> {
+        self.runtime.block_on(self.client.delete_avatar())
+    }
+
+    /// Check a link.
+    fn check_unrestrict(&self, link: String, hoster_password: Option<String>) -> Result<UnrestrictCheck, RDError> {
+        self.runtime.block_on(self.client.check_unrestrict(link, hoster_password))
+    }
+
+    /// Unrestrict a link.
+    fn unrestrict_link(&self, link: String, hoster_password: Option<String>, remote: Option<bool>) -> Result<Unrestrict, RDError> {
+        self.runtime.block_on(self.client.unrestrict_link(link, hoster_password, remote))
+    }
+
+    /// Unrestrict a folder link.
+    fn unrestrict_folder(&self, link: String) -> Result<Vec<String>, RDError> {
+        self.runtime.block_on(self.client.unrestrict_folder(link))
+    }
+
+    /// Decrypt container file (DLC, RSDF, CCF or CCF3).
+    /// Unsupported files are rejected before upload.
+    fn unrestrict_decrypt_special_folder(&self, container: ParamsContainer) -> Result<Vec<String>, RDError> {
+        self.runtime.block_on(self.client.unrestrict_decrypt_special_folder(container))
+    }
+
+    /// Decrypt container file from link.
+    fn unrestrict_decrypt_folder(&self, link: String) -> Result<Vec<String>, RDError> {
+        self.runtime.block_on(self.client.unrestrict_decrypt_folder(link))
+    }
+
+    /// Traffic informations for limited hosters.
+    fn get_traffic(&self) -> Result<Traffics, RDError> {
+        self.runtime.block_on(self.client.get_traffic())
+    }
+
+    /// Traffic details on used hosters.
+    /// start : Date(YYYY-MM-DD) and end : Date(YYYY-MM-DD).
+    fn get_traffic_details(&self, start: Option<String> , end: Option<String> ) -> Result<TrafficsPeriod, RDError> {
+        self.runtime.block_on(self.client.get_traffic_details(start, end))
+    }
+
+    /// Get transcoding links for given file.
+    fn get_streaming_transcode(&self, streaming: ParamsStreaming) -> Result<StreamingTranscode, RDError> {
+        self.runtime.block_on(self.client.get_streaming_transcode(streaming))
+    }
+
+    /// Get media informations for given file.
+    fn get_streaming_media_info(&self, streaming: ParamsStreaming) -> Result<MediaInfo, RDError> {
+        self.runtime.block_on(self.client.get_streaming_media_info(streaming))
+    }
+
+    /// Get user downloads list.
+    fn get_downloads(&self, offset: Option<u32>, page: Option<u32>, limit: Option<u32>) -> Result<Downloads, RDError> {
+        self.runtime.block_on(self.client.get_downloads(offset, page, limit))
+    }
+
+    /// Delete a link from downloads list.
+    /// Use Params with id or Download.
+    fn remove_download(&self, download: ParamsDownload) -> Result<RDOk, RDError> {
+        self.runtime.block_on(self.client.remove_download(download))
+    }
+
+    /// Get user torrents list.
+    fn get_torrents(&self, offset: Option<u32>, page: Option<u32>, limit: Option<u32>, filter: Option<String>) -> Result<Torrents, RDError> {
+        self.runtime.block_on(self.client.get_torrents(offset, page, limit, filter))
+    }
+
+    /// Get infos on torrent.
+    fn get_torrents_info(&self, torrent: ParamsTorrent) -> Result<Torrent, RDError> {
+        self.runtime.block_on(self.client.get_torrents_info(torrent))
+    }
+
+    /// Get currently active torrents number.
+    fn get_torrents_active_count(&self) -> Result<TorrentCount, RDError> {
+        self.runtime.block_on(self.client.get_torrents_active_count())
+    }
+
+    /// Get available hosts.
+    fn get_torrents_available_hosts(&self) -> Result<Vec<TorrentHost>, RDError> {
+        self.runtime.block_on(self.client.get_torrents_available_hosts())
+    }
+
+    /// Add torrent file.
+    fn add_torrent_file(&self, path: String, host: Option<ParamsTorrentHost>) -> Result<TorrentAdd, RDError> {
+        self.runtime.block_on(self.client.add_torrent_file(path, host))
+    }
+
+    /// Add magnet link, from a String or a Magnet.
+    fn add_torrent_magnet(&self, magnet: impl Into<String>, host: Option<ParamsTorrentHost>) -> Result<TorrentAdd, RDError> {
+        self.runtime.block_on(self.client.add_torrent_magnet(magnet, host))
+    }
+
+    /// Select files of a torrent.
+    fn select_torrent_file(&self, torrent: ParamsTorrent, files: ParamsTorrentFile) -> Result<(),RDError> {
+        self.runtime.block_on(self.client.select_torrent_file(torrent, files))
+    }
+
+    /// Delete a torrent from torrents list.
+    fn remove_torrent(&self, torrent: ParamsTorrent) -> Result<RDOk, RDError> {
+        self.runtime.block_on(self.client.remove_torrent(torrent))
+    }
+
+    /// Get supported hosts.
+    fn get_host(&self) -> Result<Hosts, RDError> {
+        self.runtime.block_on(self.client.get_host())
+    }
+
+    /// Get all supported regex.
+    fn get_host_regex(&self) -> Result<Vec<String>, RDError> {
+        self.runtime.block_on(self.client.get_host_regex())
+    }
+
+    /// Get all supported regex for folder links.
+    fn get_host_regex_folder(&self) -> Result<Vec<String>, RDError> {
+        self.runtime.block_on(self.client.get_host_regex_folder())
+    }
+
+    /// Get all supported domains.
+    fn get_host_domains(&self) -> Result<Vec<String>, RDError> {
+        self.runtime.block_on(self.client.get_host_domains())
+    }
+
+    /// Get status of hosters.
+    fn get_host_with_status(&self) -> Result<Hosts, RDError> {
+        self.runtime.block_on(self.client.get_host_with_status())
+    }
+
+}
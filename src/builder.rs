@@ -0,0 +1,267 @@
+use std::sync::Arc;
+use std::time::Duration;
+use reqwest::{Client, Proxy};
+use tokio_util::sync::CancellationToken;
+use crate::RDClient;
+use crate::auth_code::AuthorizationCodeFlow;
+use crate::bandwidth::BandwidthLimiter;
+use crate::credential::CredentialStore;
+use crate::data_struct::RDError;
+use crate::device_flow::DeviceAuthFlow;
+use crate::rate_limit::{RateLimiter, RateLimits};
+use crate::retry::RetryPolicy;
+use crate::session::Session;
+
+pub const BASE_URL: &str = "https://api.real-debrid.com/rest/1.0/";
+pub const OAUTH_URL: &str = "https://api.real-debrid.com/oauth/v2/";
+pub const CLIENT_ID: &str = "X245A4XAIBGVM";
+
+/// Builder for RDClient.
+/// Allow to change urls, client id and the http client used.
+#[derive(Debug, Clone)]
+pub struct RDClientBuilder {
+    base_url: String,
+    oauth_url: String,
+    client_id: String,
+    client_secret: Option<String>,
+    token: String,
+    auto_refresh: bool,
+    store: Option<Arc<dyn CredentialStore>>,
+    rate_limits: RateLimits,
+    retry: Option<RetryPolicy>,
+    bandwidth: Option<BandwidthLimiter>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    proxy: Option<Proxy>,
+    user_agent: Option<String>,
+    client: Option<Client>,
+}
+
+impl Default for RDClientBuilder {
+    fn default() -> Self {
+        RDClientBuilder {
+            base_url: BASE_URL.to_string(),
+            oauth_url: OAUTH_URL.to_string(),
+            client_id: CLIENT_ID.to_string(),
+            client_secret: None,
+            token: String::new(),
+            auto_refresh: false,
+            store: None,
+            rate_limits: RateLimits::default(),
+            retry: None,
+            bandwidth: None,
+            timeout: None,
+            connect_timeout: None,
+            proxy: None,
+            user_agent: None,
+            client: None,
+        }
+    }
+}
+
+impl RDClientBuilder {
+
+    /// Create new builder with default Real-Debrid urls.
+    pub fn new() -> RDClientBuilder {
+        RDClientBuilder::default()
+    }
+
+    /// Set api key (or access token) used by client.
+    pub fn token(mut self, api_key: impl Into<String>) -> Self {
+        self.token = api_key.into();
+        self
+    }
+
+    /// Set REST base url (default : https://api.real-debrid.com/rest/1.0/).
+    pub fn base_url(mut self, url: impl Into<String>) -> Self {
+        self.base_url = Self::with_slash(url.into());
+        self
+    }
+
+    /// Set OAuth2 base url (default : https://api.real-debrid.com/oauth/v2/).
+    pub fn oauth_url(mut self, url: impl Into<String>) -> Self {
+        self.oauth_url = Self::with_slash(url.into());
+        self
+    }
+
+    /// Set client id used for oauth2.
+    pub fn client_id(mut self, client_id: impl Into<String>) -> Self {
+        self.client_id = client_id.into();
+        self
+    }
+
+    /// Refresh oauth2 token automatically before expiry or when api return a bad token.
+    pub fn auto_refresh(mut self, auto_refresh: bool) -> Self {
+        self.auto_refresh = auto_refresh;
+        self
+    }
+
+    /// Load oauth2 session from store and save it there after auth or refresh.
+    pub fn credential_store(mut self, store: impl CredentialStore + 'static) -> Self {
+        self.store = Some(Arc::new(store));
+        self
+    }
+
+    /// Set client secret of your own application.
+    /// Device flow then use client id and client secret instead of asking new credentials.
+    pub fn client_secret(mut self, client_secret: impl Into<String>) -> Self {
+        self.client_secret = Some(client_secret.into());
+        self
+    }
+
+    /// Limit requests of client and its clones (default : 250 per minute).
+    pub fn rate_limit(mut self, requests: u32, period: Duration) -> Self {
+        self.rate_limits.global = Some(RateLimiter::new(requests, period));
+        self
+    }
+
+    /// Use a limiter shared with other clients.
+    pub fn rate_limiter(mut self, limiter: RateLimiter) -> Self {
+        self.rate_limits.global = Some(limiter);
+        self
+    }
+
+    /// Disable global rate limit.
+    pub fn no_rate_limit(mut self) -> Self {
+        self.rate_limits.global = None;
+        self
+    }
+
+    /// Limit requests on endpoints starting with given path (ex : "unrestrict/link" or "torrents/info"), in addition of global limit.
+    pub fn endpoint_rate_limit(mut self, endpoint: impl Into<String>, requests: u32, period: Duration) -> Self {
+        self.rate_limits.endpoints.push((endpoint.into(), RateLimiter::new(requests, period)));
+        self
+    }
+
+    /// Retry requests failing with a transient error (default : no retry).
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+
+    /// Limit bandwidth of all downloads done with the client (Downloader, DownloadQueue, RemoteFile).
+    /// Keep a clone of limiter to change rate later.
+    pub fn bandwidth_limit(mut self, limiter: BandwidthLimiter) -> Self {
+        self.bandwidth = Some(limiter);
+        self
+    }
+
+    /// Set total timeout of a request.
+    /// Ignored when a http client is given.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Set connect timeout.
+    /// Ignored when a http client is given.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Set proxy used for all requests.
+    /// Ignored when a http client is given.
+    pub fn proxy(mut self, proxy: Proxy) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Set user agent.
+    /// Ignored when a http client is given.
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Use a pre-built http client.
+    pub fn http_client(mut self, client: Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Create RDClient.
+    pub fn build(self) -> Result<RDClient, RDError> {
+        let client = self.http()?;
+
+        let session = Session::new(self.token, None);
+        if let Some(store) = &self.store {
+            if let Some(credentials) = store.load()? {
+                session.set(credentials.into_state());
+            }
+        }
+
+        Ok(RDClient {
+            client,
+            session: Arc::new(session),
+            auto_refresh: self.auto_refresh,
+            store: self.store,
+            rate_limits: self.rate_limits,
+            retry: self.retry,
+            bandwidth: self.bandwidth,
+            base_url: self.base_url,
+            oauth_url: self.oauth_url,
+            client_id: self.client_id,
+        })
+    }
+
+    /// Create RDClient with oauth2 (device code).
+    /// Legacy shim printing verification link on stdout, use device_flow to display it elsewhere.
+    #[deprecated(note = "use device_flow and DeviceAuthFlow, which do not print on stdout")]
+    pub async fn auth(self) -> Result<RDClient, RDError> {
+        let flow = self.device_flow().await?;
+
+        println!("Verifie device on link {} with code {}", flow.verification_url(), flow.user_code());
+
+        flow.poll_until_authorized(&CancellationToken::new()).await
+    }
+
+    /// Start oauth2 device code flow.
+    pub async fn device_flow(self) -> Result<DeviceAuthFlow, RDError> {
+        let client_secret = self.client_secret.clone();
+        DeviceAuthFlow::start(self.build()?, client_secret).await
+    }
+
+    /// Create RDClient with oauth2 (authorization code).
+    /// Need client id and client secret, give authorization link to open_url and wait redirect on a loopback redirect uri.
+    pub async fn auth_code(self, redirect_uri: impl Into<String>, open_url: impl FnOnce(&str)) -> Result<RDClient, RDError> {
+        let flow = self.authorization_code_flow(redirect_uri)?;
+        open_url(&flow.authorization_url());
+        flow.listen(&CancellationToken::new()).await
+    }
+
+    /// Start oauth2 authorization code flow.
+    pub fn authorization_code_flow(self, redirect_uri: impl Into<String>) -> Result<AuthorizationCodeFlow, RDError> {
+        let client_secret = self.client_secret.clone().ok_or(RDError::MISSING_CLIENT_SECRET)?;
+        Ok(AuthorizationCodeFlow::new(self.build()?, client_secret, redirect_uri.into()))
+    }
+
+    fn http(&self) -> Result<Client, RDError> {
+        if let Some(client) = &self.client {
+            return Ok(client.clone());
+        }
+
+        let mut builder = Client::builder();
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(proxy.clone());
+        }
+        if let Some(user_agent) = &self.user_agent {
+            builder = builder.user_agent(user_agent);
+        }
+        Ok(builder.build()?)
+    }
+
+    fn with_slash(mut url: String) -> String {
+        if !url.ends_with('/') {
+            url.push('/');
+        }
+        url
+    }
+
+}
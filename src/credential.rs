@@ -0,0 +1,143 @@
+use std::fmt::Debug;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use getset::Getters;
+use serde::{Deserialize, Serialize};
+use crate::data_struct::RDError;
+use crate::data_struct::auth::AuthRefresh;
+use crate::session::SessionState;
+use crate::state_file::write_atomic_blocking;
+
+/// OAuth2 session saved by a CredentialStore.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq, Getters)]
+pub struct Credentials {
+    #[getset(get = "pub")]
+    access_token: String,
+    #[getset(get = "pub")]
+    client_id: String,
+    #[getset(get = "pub")]
+    client_secret: String,
+    #[getset(get = "pub")]
+    refresh_token: String,
+    /// Expiration of access token in seconds since unix epoch.
+    #[getset(get = "pub")]
+    expires_at: u64,
+}
+
+impl Credentials {
+
+    pub(crate) fn from_state(state: &SessionState) -> Option<Credentials> {
+        let auth_refresh = state.refresh_authorization.as_ref()?;
+        let expires_at = auth_refresh.auth_time + Duration::from_secs(auth_refresh.expires_in);
+        Some(Credentials {
+            access_token: state.token.clone(),
+            client_id: auth_refresh.client_id.clone(),
+            client_secret: auth_refresh.client_secret.clone(),
+            refresh_token: auth_refresh.refresh_token.clone(),
+            expires_at: expires_at.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+        })
+    }
+
+    pub(crate) fn into_state(self) -> SessionState {
+        let now = SystemTime::now();
+        let expires_at = UNIX_EPOCH + Duration::from_secs(self.expires_at);
+        let auth_refresh = AuthRefresh {
+            client_id: self.client_id,
+            client_secret: self.client_secret,
+            refresh_token: self.refresh_token,
+            auth_time: now,
+            expires_in: expires_at.duration_since(now).unwrap_or_default().as_secs(),
+        };
+        SessionState { token: self.access_token, refresh_authorization: Some(auth_refresh) }
+    }
+
+}
+
+/// Storage of an OAuth2 session.
+/// RDClient write back refreshed tokens in its store.
+pub trait CredentialStore: Debug + Send + Sync {
+    fn load(&self) -> Result<Option<Credentials>, RDError>;
+    fn save(&self, credentials: &Credentials) -> Result<(), RDError>;
+    fn clear(&self) -> Result<(), RDError>;
+}
+
+/// Store credentials in a json file.
+/// File is only readable by owner and replaced atomically.
+#[derive(Debug, Clone)]
+pub struct FileCredentialStore {
+    path: PathBuf,
+}
+
+impl FileCredentialStore {
+
+    pub fn new(path: impl Into<PathBuf>) -> FileCredentialStore {
+        FileCredentialStore { path: path.into() }
+    }
+
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+}
+
+impl CredentialStore for FileCredentialStore {
+
+    fn load(&self) -> Result<Option<Credentials>, RDError> {
+        match fs::read(&self.path) {
+            Ok(bytes) => Ok(Some(serde_json::from_slice::<Credentials>(&bytes)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(RDError::IO(e)),
+        }
+    }
+
+    fn save(&self, credentials: &Credentials) -> Result<(), RDError> {
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+
+        write_atomic_blocking(&self.path, &serde_json::to_vec_pretty(credentials)?, true)
+    }
+
+    fn clear(&self) -> Result<(), RDError> {
+        match fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(RDError::IO(e)),
+            _ => Ok(()),
+        }
+    }
+
+}
+
+/// Store credentials in memory.
+/// Clones share the same credentials.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryCredentialStore {
+    credentials: Arc<Mutex<Option<Credentials>>>,
+}
+
+impl MemoryCredentialStore {
+
+    pub fn new() -> MemoryCredentialStore {
+        MemoryCredentialStore::default()
+    }
+
+}
+
+impl CredentialStore for MemoryCredentialStore {
+
+    fn load(&self) -> Result<Option<Credentials>, RDError> {
+        Ok(self.credentials.lock().unwrap_or_else(|e| e.into_inner()).clone())
+    }
+
+    fn save(&self, credentials: &Credentials) -> Result<(), RDError> {
+        *self.credentials.lock().unwrap_or_else(|e| e.into_inner()) = Some(credentials.clone());
+        Ok(())
+    }
+
+    fn clear(&self) -> Result<(), RDError> {
+        *self.credentials.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        Ok(())
+    }
+
+}
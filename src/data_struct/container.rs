@@ -0,0 +1,76 @@
+use std::path::Path;
+use tokio::io::AsyncRead;
+
+const CCF3_MAGIC: &[u8] = b"CCF3.0";
+/// Key appended by JDownloader at the end of DLC files.
+const DLC_KEY_LEN: usize = 88;
+
+/// Container files decrypted by Real-Debrid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum ContainerType {
+    DLC,
+    RSDF,
+    CCF,
+    CCF3,
+}
+
+impl ContainerType {
+
+    /// Detect type from file name extension and content.
+    /// None when content does not match extension.
+    pub fn detect(name: Option<&str>, data: &[u8]) -> Option<ContainerType> {
+        let extension = name
+            .and_then(|name| Path::new(name).extension())
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_ascii_lowercase());
+
+        match extension.as_deref() {
+            Some("dlc") => Self::is_dlc(data).then_some(ContainerType::DLC),
+            Some("rsdf") => Self::is_rsdf(data).then_some(ContainerType::RSDF),
+            // Old CCF are encrypted binary without signature.
+            Some("ccf") if data.starts_with(CCF3_MAGIC) => Some(ContainerType::CCF3),
+            Some("ccf") if !data.is_empty() => Some(ContainerType::CCF),
+            Some(_) => None,
+            None => Self::from_bytes(data),
+        }
+    }
+
+    /// Detect type from content only, old CCF can not be detected.
+    pub fn from_bytes(data: &[u8]) -> Option<ContainerType> {
+        if data.starts_with(CCF3_MAGIC) {
+            Some(ContainerType::CCF3)
+        }
+        else if Self::is_rsdf(data) {
+            Some(ContainerType::RSDF)
+        }
+        else if Self::is_dlc(data) {
+            Some(ContainerType::DLC)
+        }
+        else {
+            None
+        }
+    }
+
+    /// RSDF is hex text.
+    fn is_rsdf(data: &[u8]) -> bool {
+        let mut content = data.iter().filter(|b| !b.is_ascii_whitespace()).peekable();
+        content.peek().is_some() && content.all(|b| b.is_ascii_hexdigit())
+    }
+
+    /// DLC is base64 text followed by a key.
+    fn is_dlc(data: &[u8]) -> bool {
+        let content = data.iter().filter(|b| !b.is_ascii_whitespace()).collect::<Vec<_>>();
+        content.len() > DLC_KEY_LEN && content.iter().all(|b| b.is_ascii_alphanumeric() || matches!(b, b'+' | b'/' | b'='))
+    }
+
+}
+
+/// Container file to decrypt.
+/// Bytes and reader are detected from content, so old CCF must be given with FROM_PATH.
+#[allow(non_camel_case_types)]
+pub enum ParamsContainer {
+    FROM_PATH(String),
+    FROM_BYTES(Vec<u8>),
+    FROM_READER(Box<dyn AsyncRead + Send + Unpin>),
+}
@@ -39,6 +39,7 @@ pub struct Downloads {
 }
 
 #[allow(non_camel_case_types)]
+#[allow(clippy::large_enum_variant)]
 pub enum ParamsDownload {
     FROM_STRUCT(Download),
     FROM_ID(String)
@@ -0,0 +1,185 @@
+use std::fmt;
+use getset::Getters;
+use serde::{Deserialize, Serialize};
+
+/// Error codes documented by Real-Debrid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(non_camel_case_types)]
+pub enum ApiErrorCode {
+    INTERNAL_ERROR,
+    MISSING_PARAMETER,
+    BAD_PARAMETER_VALUE,
+    UNKNOWN_METHOD,
+    METHOD_NOT_ALLOWED,
+    SLOW_DOWN,
+    RESSOURCE_UNREACHABLE,
+    RESOURCE_NOT_FOUND,
+    BAD_TOKEN,
+    PERMISSION_DENIED,
+    TWO_FACTOR_AUTHENTICATION_NEEDED,
+    TWO_FACTOR_AUTHENTICATION_PENDING,
+    INVALID_LOGIN,
+    INVALID_PASSWORD,
+    ACCOUNT_LOCKED,
+    ACCOUNT_NOT_ACTIVATED,
+    UNSUPPORTED_HOSTER,
+    HOSTER_IN_MAINTENANCE,
+    HOSTER_LIMIT_REACHED,
+    HOSTER_TEMPORARILY_UNAVAILABLE,
+    HOSTER_NOT_AVAILABLE_FOR_FREE_USERS,
+    TOO_MANY_ACTIVE_DOWNLOADS,
+    IP_ADDRESS_NOT_ALLOWED,
+    TRAFFIC_EXHAUSTED,
+    FILE_UNAVAILABLE,
+    SERVICE_UNAVAILABLE,
+    UPLOAD_TOO_BIG,
+    UPLOAD_ERROR,
+    FILE_NOT_ALLOWED,
+    TORRENT_TOO_BIG,
+    TORRENT_FILE_INVALID,
+    ACTION_ALREADY_DONE,
+    IMAGE_RESOLUTION_ERROR,
+    TORRENT_ALREADY_ACTIVE,
+    TOO_MANY_REQUESTS,
+    INFRINGING_FILE,
+    FAIR_USAGE_LIMIT,
+    DISABLED_ENDPOINT,
+    UNKNOWN(i64),
+}
+
+impl ApiErrorCode {
+
+    /// Numeric code sent by Real-Debrid.
+    pub fn code(&self) -> i64 {
+        match self {
+            ApiErrorCode::INTERNAL_ERROR => -1,
+            ApiErrorCode::MISSING_PARAMETER => 1,
+            ApiErrorCode::BAD_PARAMETER_VALUE => 2,
+            ApiErrorCode::UNKNOWN_METHOD => 3,
+            ApiErrorCode::METHOD_NOT_ALLOWED => 4,
+            ApiErrorCode::SLOW_DOWN => 5,
+            ApiErrorCode::RESSOURCE_UNREACHABLE => 6,
+            ApiErrorCode::RESOURCE_NOT_FOUND => 7,
+            ApiErrorCode::BAD_TOKEN => 8,
+            ApiErrorCode::PERMISSION_DENIED => 9,
+            ApiErrorCode::TWO_FACTOR_AUTHENTICATION_NEEDED => 10,
+            ApiErrorCode::TWO_FACTOR_AUTHENTICATION_PENDING => 11,
+            ApiErrorCode::INVALID_LOGIN => 12,
+            ApiErrorCode::INVALID_PASSWORD => 13,
+            ApiErrorCode::ACCOUNT_LOCKED => 14,
+            ApiErrorCode::ACCOUNT_NOT_ACTIVATED => 15,
+            ApiErrorCode::UNSUPPORTED_HOSTER => 16,
+            ApiErrorCode::HOSTER_IN_MAINTENANCE => 17,
+            ApiErrorCode::HOSTER_LIMIT_REACHED => 18,
+            ApiErrorCode::HOSTER_TEMPORARILY_UNAVAILABLE => 19,
+            ApiErrorCode::HOSTER_NOT_AVAILABLE_FOR_FREE_USERS => 20,
+            ApiErrorCode::TOO_MANY_ACTIVE_DOWNLOADS => 21,
+            ApiErrorCode::IP_ADDRESS_NOT_ALLOWED => 22,
+            ApiErrorCode::TRAFFIC_EXHAUSTED => 23,
+            ApiErrorCode::FILE_UNAVAILABLE => 24,
+            ApiErrorCode::SERVICE_UNAVAILABLE => 25,
+            ApiErrorCode::UPLOAD_TOO_BIG => 26,
+            ApiErrorCode::UPLOAD_ERROR => 27,
+            ApiErrorCode::FILE_NOT_ALLOWED => 28,
+            ApiErrorCode::TORRENT_TOO_BIG => 29,
+            ApiErrorCode::TORRENT_FILE_INVALID => 30,
+            ApiErrorCode::ACTION_ALREADY_DONE => 31,
+            ApiErrorCode::IMAGE_RESOLUTION_ERROR => 32,
+            ApiErrorCode::TORRENT_ALREADY_ACTIVE => 33,
+            ApiErrorCode::TOO_MANY_REQUESTS => 34,
+            ApiErrorCode::INFRINGING_FILE => 35,
+            ApiErrorCode::FAIR_USAGE_LIMIT => 36,
+            ApiErrorCode::DISABLED_ENDPOINT => 37,
+            ApiErrorCode::UNKNOWN(code) => *code,
+        }
+    }
+
+}
+
+impl From<i64> for ApiErrorCode {
+    fn from(code: i64) -> Self {
+        match code {
+            -1 => ApiErrorCode::INTERNAL_ERROR,
+            1 => ApiErrorCode::MISSING_PARAMETER,
+            2 => ApiErrorCode::BAD_PARAMETER_VALUE,
+            3 => ApiErrorCode::UNKNOWN_METHOD,
+            4 => ApiErrorCode::METHOD_NOT_ALLOWED,
+            5 => ApiErrorCode::SLOW_DOWN,
+            6 => ApiErrorCode::RESSOURCE_UNREACHABLE,
+            7 => ApiErrorCode::RESOURCE_NOT_FOUND,
+            8 => ApiErrorCode::BAD_TOKEN,
+            9 => ApiErrorCode::PERMISSION_DENIED,
+            10 => ApiErrorCode::TWO_FACTOR_AUTHENTICATION_NEEDED,
+            11 => ApiErrorCode::TWO_FACTOR_AUTHENTICATION_PENDING,
+            12 => ApiErrorCode::INVALID_LOGIN,
+            13 => ApiErrorCode::INVALID_PASSWORD,
+            14 => ApiErrorCode::ACCOUNT_LOCKED,
+            15 => ApiErrorCode::ACCOUNT_NOT_ACTIVATED,
+            16 => ApiErrorCode::UNSUPPORTED_HOSTER,
+            17 => ApiErrorCode::HOSTER_IN_MAINTENANCE,
+            18 => ApiErrorCode::HOSTER_LIMIT_REACHED,
+            19 => ApiErrorCode::HOSTER_TEMPORARILY_UNAVAILABLE,
+            20 => ApiErrorCode::HOSTER_NOT_AVAILABLE_FOR_FREE_USERS,
+            21 => ApiErrorCode::TOO_MANY_ACTIVE_DOWNLOADS,
+            22 => ApiErrorCode::IP_ADDRESS_NOT_ALLOWED,
+            23 => ApiErrorCode::TRAFFIC_EXHAUSTED,
+            24 => ApiErrorCode::FILE_UNAVAILABLE,
+            25 => ApiErrorCode::SERVICE_UNAVAILABLE,
+            26 => ApiErrorCode::UPLOAD_TOO_BIG,
+            27 => ApiErrorCode::UPLOAD_ERROR,
+            28 => ApiErrorCode::FILE_NOT_ALLOWED,
+            29 => ApiErrorCode::TORRENT_TOO_BIG,
+            30 => ApiErrorCode::TORRENT_FILE_INVALID,
+            31 => ApiErrorCode::ACTION_ALREADY_DONE,
+            32 => ApiErrorCode::IMAGE_RESOLUTION_ERROR,
+            33 => ApiErrorCode::TORRENT_ALREADY_ACTIVE,
+            34 => ApiErrorCode::TOO_MANY_REQUESTS,
+            35 => ApiErrorCode::INFRINGING_FILE,
+            36 => ApiErrorCode::FAIR_USAGE_LIMIT,
+            37 => ApiErrorCode::DISABLED_ENDPOINT,
+            code => ApiErrorCode::UNKNOWN(code),
+        }
+    }
+}
+
+/// Error returned by Real-Debrid in body of response.
+#[derive(Debug, Clone, Getters)]
+pub struct ApiError {
+    #[getset(get = "pub")]
+    code: ApiErrorCode,
+    #[getset(get = "pub")]
+    message: String,
+    #[getset(get = "pub")]
+    details: Option<String>,
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (code {})", self.message, self.code.code())?;
+        if let Some(details) = &self.details {
+            write!(f, " : {}", details)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct ApiErrorBody {
+    pub(crate) error: String,
+    pub(crate) error_code: i64,
+    pub(crate) error_details: Option<String>,
+}
+
+impl From<ApiErrorBody> for ApiError {
+    fn from(body: ApiErrorBody) -> Self {
+        ApiError { code: ApiErrorCode::from(body.error_code), message: body.error, details: body.error_details }
+    }
+}
+
+/*
+{
+    "error": "string", // Main error message
+    "error_code": int, // Error code, see list
+    "error_details": "string" // Optional, details on the error
+}
+ */
@@ -1,31 +1,229 @@
 pub mod user;
+pub mod settings;
 pub mod traffic;
 pub mod download;
 pub mod host;
 pub mod torrent;
 pub mod streaming;
 pub mod unrestrict;
+pub mod error;
+pub mod container;
 pub(crate) mod auth;
 
+use std::fmt;
+use reqwest::{Response, StatusCode};
+use crate::data_struct::error::{ApiError, ApiErrorBody, ApiErrorCode};
+use crate::data_struct::torrent::TorrentStatus;
+
 #[derive(Debug)]
 #[allow(non_camel_case_types)]
 pub enum RDError {
+    /// Never returned, unexpected responses are HTTP_STATUS or API.
+    #[deprecated(note = "match HTTP_STATUS or API instead")]
     UNDEFINED,
     BAD_TOKEN,
     PERMISSION_DENIED,
     NO_CONTENT,
     UNKNOWN_RESSOURCE,
+    /// Hoster not available for free users (error code 20).
     NOT_PREMIUM,
     BAD_REQUEST,
     SERVICE_UNAVAILABLE,
     PATH_NOT_RIGHT,
     ACTION_ALREADY_DONE,
+    /// Never returned, torrents whose magnet can not be converted end in an error status.
+    #[deprecated(note = "match TORRENT_FAILED or SERVICE_UNAVAILABLE instead")]
     PROBLEM_FINDING_METADATA,
+    /// Unavailable file (error code 24).
     FILE_UNAVAILABLE,
     AUTH_FAILED,
+    AUTH_EXPIRED,
+    AUTH_CANCELLED,
+    MISSING_CLIENT_SECRET,
+    BAD_REDIRECT_URI,
     REFRESH_FAILED,
     NOT_REFRESH_TOKEN,
     NOT_OAUTH2,
+    NO_CREDENTIALS,
+    UNSUPPORTED_CONTAINER,
+    /// Bytes received differ from file size.
+    SIZE_MISMATCH { expected: u64, actual: u64 },
+    /// Remote file changed since partial download.
+    FILE_CHANGED,
+    /// Time out of day or window without duration.
+    INVALID_TIME_WINDOW,
+    /// Size of remote file is not known.
+    UNKNOWN_SIZE,
+    /// Server sent another range than requested.
+    RANGE_MISMATCH,
+    /// Torrent ended in an error status.
+    TORRENT_FAILED(TorrentStatus),
+    /// No file of torrent matched selection rules.
+    NO_FILE_SELECTED,
+    /// Torrent did not reach expected status in time.
+    TIMEOUT,
+    /// Corrupt .torrent file.
+    INVALID_TORRENT(String),
+    /// Magnet link which can not be parsed.
+    INVALID_MAGNET(String),
+    /// Patterns of supported hosts which can not be compiled together.
+    INVALID_PATTERN(regex::Error),
+    /// Request could not be sent or response could not be read.
+    TRANSPORT(reqwest::Error),
+    /// Response body is not the expected json.
+    DECODE(serde_json::Error),
+    /// Unexpected http status without Real-Debrid error body.
+    HTTP_STATUS(StatusCode),
+    /// Error sent by Real-Debrid.
+    API(ApiError),
+    IO(std::io::Error),
+}
+
+impl RDError {
+
+    /// Create error from a response which is not a success.
+    /// Use Real-Debrid error body when present, else http status.
+    pub(crate) async fn from_response(response: Response) -> RDError {
+        let status = response.status();
+        match response.bytes().await {
+            Ok(bytes) => match serde_json::from_slice::<ApiErrorBody>(&bytes) {
+                Ok(body) => RDError::from_api(ApiError::from(body)),
+                Err(_) => RDError::from_status(status),
+            },
+            Err(e) => RDError::TRANSPORT(e),
+        }
+    }
+
+    /// Create error from Real-Debrid error body.
+    /// Codes with their own variant use it, so they match the same way with or without body.
+    pub fn from_api(error: ApiError) -> RDError {
+        match error.code() {
+            ApiErrorCode::BAD_TOKEN => RDError::BAD_TOKEN,
+            ApiErrorCode::PERMISSION_DENIED => RDError::PERMISSION_DENIED,
+            ApiErrorCode::RESOURCE_NOT_FOUND => RDError::UNKNOWN_RESSOURCE,
+            ApiErrorCode::HOSTER_NOT_AVAILABLE_FOR_FREE_USERS => RDError::NOT_PREMIUM,
+            ApiErrorCode::FILE_UNAVAILABLE => RDError::FILE_UNAVAILABLE,
+            ApiErrorCode::SERVICE_UNAVAILABLE => RDError::SERVICE_UNAVAILABLE,
+            ApiErrorCode::ACTION_ALREADY_DONE => RDError::ACTION_ALREADY_DONE,
+            _ => RDError::API(error),
+        }
+    }
+
+    /// Create error from http status.
+    pub fn from_status(status: StatusCode) -> RDError {
+        match status {
+            StatusCode::UNAUTHORIZED => RDError::BAD_TOKEN,
+            StatusCode::FORBIDDEN => RDError::PERMISSION_DENIED,
+            StatusCode::NOT_FOUND => RDError::UNKNOWN_RESSOURCE,
+            StatusCode::BAD_REQUEST => RDError::BAD_REQUEST,
+            StatusCode::SERVICE_UNAVAILABLE => RDError::SERVICE_UNAVAILABLE,
+            status => RDError::HTTP_STATUS(status),
+        }
+    }
+
+    /// Check if error may disappear by sending request again (connection problem, overload, outage).
+    pub fn is_transient(&self) -> bool {
+        match self {
+            RDError::TRANSPORT(e) => e.is_connect() || e.is_timeout() || e.is_request() || e.is_body(),
+            RDError::SERVICE_UNAVAILABLE => true,
+            RDError::HTTP_STATUS(status) => *status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error(),
+            RDError::API(e) => matches!(e.code(),
+                ApiErrorCode::INTERNAL_ERROR | ApiErrorCode::SLOW_DOWN | ApiErrorCode::RESSOURCE_UNREACHABLE
+                | ApiErrorCode::SERVICE_UNAVAILABLE | ApiErrorCode::TOO_MANY_REQUESTS),
+            _ => false,
+        }
+    }
+
+    /// Real-Debrid error code if error come from api.
+    pub fn api_code(&self) -> Option<ApiErrorCode> {
+        match self {
+            RDError::API(e) => Some(*e.code()),
+            RDError::BAD_TOKEN => Some(ApiErrorCode::BAD_TOKEN),
+            RDError::PERMISSION_DENIED => Some(ApiErrorCode::PERMISSION_DENIED),
+            RDError::UNKNOWN_RESSOURCE => Some(ApiErrorCode::RESOURCE_NOT_FOUND),
+            RDError::NOT_PREMIUM => Some(ApiErrorCode::HOSTER_NOT_AVAILABLE_FOR_FREE_USERS),
+            RDError::FILE_UNAVAILABLE => Some(ApiErrorCode::FILE_UNAVAILABLE),
+            RDError::SERVICE_UNAVAILABLE => Some(ApiErrorCode::SERVICE_UNAVAILABLE),
+            RDError::ACTION_ALREADY_DONE => Some(ApiErrorCode::ACTION_ALREADY_DONE),
+            _ => None,
+        }
+    }
+
+}
+
+impl fmt::Display for RDError {
+    #[allow(deprecated)]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RDError::UNDEFINED => write!(f, "undefined error"),
+            RDError::BAD_TOKEN => write!(f, "bad token"),
+            RDError::PERMISSION_DENIED => write!(f, "permission denied"),
+            RDError::NO_CONTENT => write!(f, "no content"),
+            RDError::UNKNOWN_RESSOURCE => write!(f, "unknown ressource"),
+            RDError::NOT_PREMIUM => write!(f, "premium account needed"),
+            RDError::BAD_REQUEST => write!(f, "bad request"),
+            RDError::SERVICE_UNAVAILABLE => write!(f, "service unavailable"),
+            RDError::PATH_NOT_RIGHT => write!(f, "path not found"),
+            RDError::ACTION_ALREADY_DONE => write!(f, "action already done"),
+            RDError::PROBLEM_FINDING_METADATA => write!(f, "problem finding metadata"),
+            RDError::FILE_UNAVAILABLE => write!(f, "file unavailable"),
+            RDError::AUTH_FAILED => write!(f, "authentication failed"),
+            RDError::AUTH_EXPIRED => write!(f, "device code expired"),
+            RDError::AUTH_CANCELLED => write!(f, "authentication cancelled"),
+            RDError::MISSING_CLIENT_SECRET => write!(f, "client secret is required"),
+            RDError::BAD_REDIRECT_URI => write!(f, "redirect uri must be a loopback url with port"),
+            RDError::REFRESH_FAILED => write!(f, "refresh of token failed"),
+            RDError::NOT_REFRESH_TOKEN => write!(f, "no refresh token"),
+            RDError::NOT_OAUTH2 => write!(f, "client not authenticated with oauth2"),
+            RDError::NO_CREDENTIALS => write!(f, "no credentials in store"),
+            RDError::UNSUPPORTED_CONTAINER => write!(f, "unsupported container file (expected DLC, RSDF, CCF or CCF3)"),
+            RDError::SIZE_MISMATCH { expected, actual } => write!(f, "size mismatch : expected {} bytes, received {}", expected, actual),
+            RDError::FILE_CHANGED => write!(f, "remote file changed since partial download"),
+            RDError::INVALID_TIME_WINDOW => write!(f, "time window needs times of day and a duration"),
+            RDError::UNKNOWN_SIZE => write!(f, "size of remote file is unknown"),
+            RDError::RANGE_MISMATCH => write!(f, "server sent another range than requested"),
+            RDError::TORRENT_FAILED(status) => write!(f, "torrent failed with status {}", status.as_str()),
+            RDError::NO_FILE_SELECTED => write!(f, "no file matched selection rules"),
+            RDError::TIMEOUT => write!(f, "timeout waiting for torrent"),
+            RDError::INVALID_TORRENT(e) => write!(f, "invalid torrent file : {}", e),
+            RDError::INVALID_MAGNET(e) => write!(f, "invalid magnet link : {}", e),
+            RDError::INVALID_PATTERN(e) => write!(f, "invalid host patterns : {}", e),
+            RDError::TRANSPORT(e) => write!(f, "transport error : {}", e),
+            RDError::DECODE(e) => write!(f, "decode error : {}", e),
+            RDError::HTTP_STATUS(status) => write!(f, "unexpected http status : {}", status),
+            RDError::API(e) => write!(f, "api error : {}", e),
+            RDError::IO(e) => write!(f, "io error : {}", e),
+        }
+    }
+}
+
+impl std::error::Error for RDError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RDError::TRANSPORT(e) => Some(e),
+            RDError::DECODE(e) => Some(e),
+            RDError::IO(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for RDError {
+    fn from(e: reqwest::Error) -> Self {
+        RDError::TRANSPORT(e)
+    }
+}
+
+impl From<serde_json::Error> for RDError {
+    fn from(e: serde_json::Error) -> Self {
+        RDError::DECODE(e)
+    }
+}
+
+impl From<std::io::Error> for RDError {
+    fn from(e: std::io::Error) -> Self {
+        RDError::IO(e)
+    }
 }
 
 #[derive(Debug)]
@@ -35,4 +233,4 @@ pub enum RDOk {
     REMOVED_SUCCESS,
     ADDED_SUCCESS,
     AUTH_REFRESH,
-}
\ No newline at end of file
+}
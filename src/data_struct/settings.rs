@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+use getset::Getters;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Getters)]
+pub struct Settings {
+    #[getset(get = "pub")]
+    download_ports: Vec<String>,
+    #[getset(get = "pub")]
+    download_port: String,
+    #[getset(get = "pub")]
+    locales: HashMap<String, String>,
+    #[getset(get = "pub")]
+    locale: String,
+    #[getset(get = "pub")]
+    streaming_qualities: Vec<String>,
+    #[getset(get = "pub")]
+    streaming_quality: String,
+    #[getset(get = "pub")]
+    mobile_streaming_quality: String,
+    #[getset(get = "pub")]
+    streaming_languages: HashMap<String, String>,
+    #[getset(get = "pub")]
+    streaming_language_preference: String,
+    #[getset(get = "pub")]
+    streaming_cast_audio: Vec<String>,
+    #[getset(get = "pub")]
+    streaming_cast_audio_preference: String,
+}
+
+/// Setting to update, with a value from the matching list of Settings.
+#[derive(Debug, Clone)]
+#[allow(non_camel_case_types)]
+pub enum ParamsSetting {
+    /// Value from download_ports.
+    DOWNLOAD_PORT(String),
+    /// Key of locales.
+    LOCALE(String),
+    /// Key of streaming_languages.
+    STREAMING_LANGUAGE_PREFERENCE(String),
+    /// Value from streaming_qualities.
+    STREAMING_QUALITY(String),
+    /// Value from streaming_qualities.
+    MOBILE_STREAMING_QUALITY(String),
+    /// Value from streaming_cast_audio.
+    STREAMING_CAST_AUDIO_PREFERENCE(String),
+}
+
+impl ParamsSetting {
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            ParamsSetting::DOWNLOAD_PORT(_) => "download_port",
+            ParamsSetting::LOCALE(_) => "locale",
+            ParamsSetting::STREAMING_LANGUAGE_PREFERENCE(_) => "streaming_language_preference",
+            ParamsSetting::STREAMING_QUALITY(_) => "streaming_quality",
+            ParamsSetting::MOBILE_STREAMING_QUALITY(_) => "mobile_streaming_quality",
+            ParamsSetting::STREAMING_CAST_AUDIO_PREFERENCE(_) => "streaming_cast_audio_preference",
+        }
+    }
+
+    pub(crate) fn value(self) -> String {
+        match self {
+            ParamsSetting::DOWNLOAD_PORT(d)
+            | ParamsSetting::LOCALE(d)
+            | ParamsSetting::STREAMING_LANGUAGE_PREFERENCE(d)
+            | ParamsSetting::STREAMING_QUALITY(d)
+            | ParamsSetting::MOBILE_STREAMING_QUALITY(d)
+            | ParamsSetting::STREAMING_CAST_AUDIO_PREFERENCE(d) => d,
+        }
+    }
+
+}
+
+/*
+{
+    "download_ports": [ // Possible "download_port" value to update settings
+        "string",
+        "string",
+        ...
+    ],
+    "download_port": "string", // Current user download port
+    "locales": { // Possible "locale" value to update settings
+        "string": "string",
+        "string": "string",
+        ...
+    },
+    "locale": "string", // Current user locale
+    "streaming_qualities": [ // Possible "streaming_quality" value to update settings
+        "string",
+        "string",
+        ...
+    ],
+    "streaming_quality": "string", // Current user streaming quality
+    "mobile_streaming_quality": "string", // Current user streaming quality on mobile devices
+    "streaming_languages": { // Possible "streaming_language_preference" value to update settings
+        "string": "string",
+        "string": "string",
+        ...
+    },
+    "streaming_language_preference": "string", // Current user streaming language preference
+    "streaming_cast_audio": [ // Possible "streaming_cast_audio_preference" value to update settings
+        "string",
+        "string",
+        ...
+    ],
+    "streaming_cast_audio_preference": "string" // Current user audio preference on Google Cast devices
+}
+ */
@@ -72,6 +72,7 @@ pub struct Torrents {
 }
 
 #[allow(non_camel_case_types)]
+#[allow(clippy::large_enum_variant)]
 pub enum ParamsTorrent {
     FROM_STRUCT(Torrent),
     FROM_ADD(TorrentAdd),
@@ -114,6 +115,87 @@ pub enum ParamsTorrent {
     }
 */
 
+impl Torrent {
+
+    /// Typed status.
+    pub fn torrent_status(&self) -> TorrentStatus {
+        TorrentStatus::from(self.status.as_str())
+    }
+
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[allow(non_camel_case_types)]
+pub enum TorrentStatus {
+    MAGNET_ERROR,
+    MAGNET_CONVERSION,
+    WAITING_FILES_SELECTION,
+    QUEUED,
+    DOWNLOADING,
+    DOWNLOADED,
+    ERROR,
+    VIRUS,
+    COMPRESSING,
+    UPLOADING,
+    DEAD,
+    UNKNOWN(String),
+}
+
+impl TorrentStatus {
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            TorrentStatus::MAGNET_ERROR => "magnet_error",
+            TorrentStatus::MAGNET_CONVERSION => "magnet_conversion",
+            TorrentStatus::WAITING_FILES_SELECTION => "waiting_files_selection",
+            TorrentStatus::QUEUED => "queued",
+            TorrentStatus::DOWNLOADING => "downloading",
+            TorrentStatus::DOWNLOADED => "downloaded",
+            TorrentStatus::ERROR => "error",
+            TorrentStatus::VIRUS => "virus",
+            TorrentStatus::COMPRESSING => "compressing",
+            TorrentStatus::UPLOADING => "uploading",
+            TorrentStatus::DEAD => "dead",
+            TorrentStatus::UNKNOWN(d) => d,
+        }
+    }
+
+    /// Links are ready.
+    pub fn is_finished(&self) -> bool {
+        *self == TorrentStatus::DOWNLOADED
+    }
+
+    /// Torrent will never be downloaded.
+    pub fn is_failed(&self) -> bool {
+        matches!(self, TorrentStatus::MAGNET_ERROR | TorrentStatus::ERROR | TorrentStatus::VIRUS | TorrentStatus::DEAD)
+    }
+
+    /// Status will not change anymore.
+    pub fn is_terminal(&self) -> bool {
+        self.is_finished() || self.is_failed()
+    }
+
+}
+
+impl From<&str> for TorrentStatus {
+    fn from(status: &str) -> Self {
+        match status {
+            "magnet_error" => TorrentStatus::MAGNET_ERROR,
+            "magnet_conversion" => TorrentStatus::MAGNET_CONVERSION,
+            "waiting_files_selection" => TorrentStatus::WAITING_FILES_SELECTION,
+            "queued" => TorrentStatus::QUEUED,
+            "downloading" => TorrentStatus::DOWNLOADING,
+            "downloaded" => TorrentStatus::DOWNLOADED,
+            "error" => TorrentStatus::ERROR,
+            "virus" => TorrentStatus::VIRUS,
+            "compressing" => TorrentStatus::COMPRESSING,
+            "uploading" => TorrentStatus::UPLOADING,
+            "dead" => TorrentStatus::DEAD,
+            status => TorrentStatus::UNKNOWN(status.to_string()),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Default , Debug, Clone, Getters)]
 pub struct TorrentCount {
     #[getset(get = "pub")]
@@ -0,0 +1,208 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use crate::{RDClient, RDTraitAsync};
+use crate::data_struct::RDError;
+use crate::data_struct::torrent::{ParamsTorrent, ParamsTorrentHost, Torrent, TorrentAdd};
+use crate::magnet::Magnet;
+use crate::pagination::{PageOptions, MAX_PAGE_SIZE};
+use crate::torrent_meta::TorrentMeta;
+use crate::ttl_cache::TtlCache;
+
+pub const DEFAULT_INDEX_TTL: Duration = Duration::from_secs(300);
+
+/// Torrent to add without duplicate.
+#[allow(non_camel_case_types)]
+pub enum ParamsTorrentSource {
+    FROM_MAGNET(Magnet),
+    /// Path of a .torrent file.
+    FROM_PATH(String),
+}
+
+impl From<Magnet> for ParamsTorrentSource {
+    fn from(value: Magnet) -> Self {
+        ParamsTorrentSource::FROM_MAGNET(value)
+    }
+}
+
+/// Result of add_torrent_dedup.
+#[derive(Debug, Clone)]
+#[allow(non_camel_case_types)]
+pub enum DedupResult {
+    /// Torrent with same hash already in account.
+    EXISTING(Box<Torrent>),
+    ADDED(TorrentAdd),
+}
+
+/// Index of torrents of account by hash, fetched again after ttl.
+/// Clones share the same index.
+#[derive(Debug, Clone)]
+pub struct TorrentIndex {
+    client: RDClient,
+    ttl: Duration,
+    readd_failed: bool,
+    /// Torrents of account by hash.
+    torrents: TtlCache<Arc<HashMap<String, Torrent>>>,
+    /// Lock of each hash being added, so a hash is looked up and added by one task at a time.
+    adding: Arc<Mutex<HashLocks>>,
+}
+
+type HashLocks = HashMap<String, Arc<tokio::sync::Mutex<()>>>;
+
+/// Lock of a hash being added, removed by last task of hash when it ends or is cancelled.
+struct HashLock<'a> {
+    lock: Arc<tokio::sync::Mutex<()>>,
+    adding: &'a Mutex<HashLocks>,
+    hash: String,
+}
+
+impl Drop for HashLock<'_> {
+    fn drop(&mut self) {
+        let mut adding = self.adding.lock().unwrap_or_else(|e| e.into_inner());
+        // Only held by map and this task.
+        if Arc::strong_count(&self.lock) == 2 {
+            adding.remove(&self.hash);
+        }
+    }
+}
+
+impl TorrentIndex {
+
+    pub fn new(client: RDClient) -> TorrentIndex {
+        TorrentIndex { client, ttl: DEFAULT_INDEX_TTL, readd_failed: false, torrents: TtlCache::new(), adding: Arc::new(Mutex::new(HashMap::new())) }
+    }
+
+    /// Time before torrents are fetched again (default : 5min).
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Add again torrents whose existing copy is dead or in error (default : false).
+    pub fn readd_failed(mut self, readd_failed: bool) -> Self {
+        self.readd_failed = readd_failed;
+        self
+    }
+
+    /// Torrent of account with info-hash, torrents are fetched on first call and when expired.
+    pub async fn find(&self, hash: &str) -> Result<Option<Torrent>, RDError> {
+        let torrents = self.torrents.get(self.ttl, || self.fetch()).await?;
+        Ok(torrents.get(&hash.to_ascii_lowercase()).cloned())
+    }
+
+    /// Fetch torrents now.
+    pub async fn refresh(&self) -> Result<(), RDError> {
+        self.torrents.set(self.fetch().await?).await;
+        Ok(())
+    }
+
+    /// Add a magnet or .torrent file unless a torrent with same info-hash is in account.
+    /// Existing torrent is returned instead, unless it failed and readd_failed is set.
+    /// Calls for a same hash wait each other, so it is added once.
+    pub async fn add_torrent_dedup(&self, source: impl Into<ParamsTorrentSource>, host: Option<ParamsTorrentHost>) -> Result<DedupResult, RDError> {
+        let source = source.into();
+        let hash = match &source {
+            ParamsTorrentSource::FROM_MAGNET(d) => d.info_hash().clone(),
+            ParamsTorrentSource::FROM_PATH(d) => TorrentMeta::from_path(d).await?.info_hash().clone(),
+        };
+
+        let lock = HashLock { lock: self.adding().entry(hash.clone()).or_default().clone(), adding: &self.adding, hash };
+        let _guard = lock.lock.lock().await;
+        self.add_unless_found(&lock.hash, source, host).await
+    }
+
+    async fn add_unless_found(&self, hash: &str, source: ParamsTorrentSource, host: Option<ParamsTorrentHost>) -> Result<DedupResult, RDError> {
+        if let Some(torrent) = self.find(hash).await? {
+            if !(self.readd_failed && torrent.torrent_status().is_failed()) {
+                return Ok(DedupResult::EXISTING(Box::new(torrent)));
+            }
+        }
+
+        let added = match source {
+            ParamsTorrentSource::FROM_MAGNET(d) => self.client.add_torrent_magnet(d, host).await?,
+            ParamsTorrentSource::FROM_PATH(d) => self.client.add_torrent_file(d, host).await?,
+        };
+
+        // Index new torrent so next submissions find it before ttl.
+        let info = self.client.get_torrents_info(ParamsTorrent::FROM_ID(added.id().clone())).await;
+        self.torrents.update(|cached| match (info, cached.as_mut()) {
+            (Ok(torrent), Some((torrents, _))) => {
+                Arc::make_mut(torrents).insert(torrent.hash().to_ascii_lowercase(), torrent);
+            },
+            _ => *cached = None,
+        }).await;
+        Ok(DedupResult::ADDED(added))
+    }
+
+    fn adding(&self) -> std::sync::MutexGuard<'_, HashLocks> {
+        self.adding.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    async fn fetch(&self) -> Result<Arc<HashMap<String, Torrent>>, RDError> {
+        let torrents = self.client.get_all_torrents(PageOptions::new().page_size(MAX_PAGE_SIZE)).await?;
+        Ok(Arc::new(Self::index(torrents)))
+    }
+
+    /// Torrents by lowercase hash.
+    /// Keep a working torrent rather than a failed one when hash is present twice.
+    fn index(torrents: Vec<Torrent>) -> HashMap<String, Torrent> {
+        let mut index = HashMap::with_capacity(torrents.len());
+        for torrent in torrents {
+            let hash = torrent.hash().to_ascii_lowercase();
+            let failed = |d: &Torrent| d.torrent_status().is_failed();
+            if index.get(&hash).is_none_or(|existing: &Torrent| failed(existing) && !failed(&torrent)) {
+                index.insert(hash, torrent);
+            }
+        }
+        index
+    }
+
+}
+
+impl RDClient {
+
+    /// Create index of torrents of account to add torrents without duplicates.
+    pub fn torrent_index(&self) -> TorrentIndex {
+        TorrentIndex::new(self.clone())
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn torrent(id: &str, hash: &str, status: &str) -> Torrent {
+        let mut value = serde_json::to_value(Torrent::default()).unwrap();
+        value["id"] = id.into();
+        value["hash"] = hash.into();
+        value["status"] = status.into();
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[tokio::test]
+    async fn dedup_index() {
+        let index = TorrentIndex::index(vec![
+            torrent("A1", "AAAA", "dead"),
+            torrent("A2", "aaaa", "downloaded"),
+            torrent("A3", "aaaa", "downloading"),
+            torrent("B1", "BBBB", "downloading"),
+            torrent("B2", "bbbb", "error"),
+            torrent("C1", "cccc", "virus"),
+            torrent("C2", "cccc", "magnet_error"),
+        ]);
+        let id = |hash: &str| index.get(hash).map(|d| d.id().as_str());
+        // Failed torrent replaced by first working one, working one never replaced.
+        assert_eq!(id("aaaa"), Some("A2"));
+        assert_eq!(id("bbbb"), Some("B1"));
+        assert_eq!(id("cccc"), Some("C1"));
+        assert_eq!(id("AAAA"), None);
+
+        // Cached index is used before ttl, hash is matched in any case.
+        let dedup = TorrentIndex::new(RDClient::default());
+        dedup.torrents.set(Arc::new(index)).await;
+        assert_eq!(dedup.find("AAAA").await.unwrap().map(|d| d.id().clone()), Some("A2".to_string()));
+        assert!(dedup.find("dddd").await.unwrap().is_none());
+    }
+
+}
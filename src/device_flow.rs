@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+use crate::RDClient;
+use crate::data_struct::RDError;
+use crate::data_struct::auth::{AuthCredential, AuthDevice, AuthToken};
+
+/// Status of a device code flow.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum DeviceAuthEvent {
+    /// Waiting for user to enter code, with time left before code expire.
+    PENDING(Duration),
+    /// User approved device.
+    APPROVED,
+    AUTHORIZED,
+    EXPIRED,
+    CANCELLED,
+}
+
+/// OAuth2 device code flow.
+/// Show user_code and verification_url to user then wait with poll_until_authorized.
+#[derive(Debug)]
+pub struct DeviceAuthFlow {
+    client: RDClient,
+    client_secret: Option<String>,
+    device: AuthDevice,
+    started: Instant,
+    events: broadcast::Sender<DeviceAuthEvent>,
+}
+
+impl DeviceAuthFlow {
+
+    /// Ask a device code for client.
+    /// Without client secret, new credentials are asked for this device.
+    pub(crate) async fn start(client: RDClient, client_secret: Option<String>) -> Result<DeviceAuthFlow, RDError> {
+        let mut link = format!("{}device/code?client_id={}", client.oauth_url, client.client_id);
+        if client_secret.is_none() {
+            link.push_str("&new_credentials=yes");
+        }
+        let response = client.send_public(client.client.get(link)).await?;
+        if !response.status().is_success() {
+            return Err(RDError::AUTH_FAILED);
+        }
+
+        let device = RDClient::parse::<AuthDevice>(response).await?;
+        let (events, _) = broadcast::channel(16);
+
+        Ok(DeviceAuthFlow { client, client_secret, device, started: Instant::now(), events })
+    }
+
+    /// Code to enter on verification url.
+    pub fn user_code(&self) -> &str {
+        &self.device.user_code
+    }
+
+    pub fn verification_url(&self) -> &str {
+        &self.device.verification_url
+    }
+
+    /// Verification url with code already filled.
+    pub fn direct_verification_url(&self) -> &str {
+        &self.device.direct_verification_url
+    }
+
+    /// Time left before code expire.
+    pub fn expires_in(&self) -> Duration {
+        Duration::from_secs(self.device.expires_in).saturating_sub(self.started.elapsed())
+    }
+
+    /// Receive status of flow while polling.
+    pub fn subscribe(&self) -> broadcast::Receiver<DeviceAuthEvent> {
+        self.events.subscribe()
+    }
+
+    /// Wait until user approve device then create token.
+    /// Stop with AUTH_EXPIRED when code expire and AUTH_CANCELLED when cancel is triggered.
+    pub async fn poll_until_authorized(self, cancel: &CancellationToken) -> Result<RDClient, RDError> {
+        let deadline = self.started + Duration::from_secs(self.device.expires_in);
+        let interval = Duration::from_secs(self.device.interval.max(1));
+
+        let (credential, token) = loop {
+            let now = Instant::now();
+            if now >= deadline {
+                self.emit(DeviceAuthEvent::EXPIRED);
+                return Err(RDError::AUTH_EXPIRED);
+            }
+            self.emit(DeviceAuthEvent::PENDING(deadline - now));
+
+            let polled = tokio::select! {
+                _ = cancel.cancelled() => None,
+                polled = self.poll() => Some(polled?),
+            };
+            match polled {
+                None => {
+                    self.emit(DeviceAuthEvent::CANCELLED);
+                    return Err(RDError::AUTH_CANCELLED);
+                },
+                Some(Some(authorized)) => break authorized,
+                Some(None) => {},
+            }
+
+            tokio::select! {
+                _ = cancel.cancelled() => {
+                    self.emit(DeviceAuthEvent::CANCELLED);
+                    return Err(RDError::AUTH_CANCELLED);
+                },
+                _ = tokio::time::sleep_until(deadline.min(Instant::now() + interval)) => {},
+            }
+        };
+
+        self.emit(DeviceAuthEvent::APPROVED);
+
+        self.client.start_session(credential.client_id, credential.client_secret, token).await?;
+
+        self.emit(DeviceAuthEvent::AUTHORIZED);
+        Ok(self.client)
+    }
+
+    /// Check once if user approved device and create token.
+    async fn poll(&self) -> Result<Option<(AuthCredential, AuthToken)>, RDError> {
+        let credential = match &self.client_secret {
+            Some(client_secret) => AuthCredential { client_id: self.client.client_id.clone(), client_secret: client_secret.clone() },
+            None => {
+                let link = format!("{}device/credentials?client_id={}&code={}", self.client.oauth_url, self.client.client_id, self.device.device_code);
+                let response = self.client.send_public(self.client.client.get(link)).await?;
+                if !response.status().is_success() {
+                    return Ok(None);
+                }
+                RDClient::parse::<AuthCredential>(response).await?
+            },
+        };
+
+        let mut params = HashMap::new();
+        params.insert("client_id", credential.client_id.to_string());
+        params.insert("client_secret", credential.client_secret.to_string());
+        params.insert("code", self.device.device_code.clone());
+        params.insert("grant_type", "http://oauth.net/grant_type/device/1.0".to_string());
+
+        match self.client.request_token(&params).await {
+            Ok(token) => Ok(Some((credential, token))),
+            Err(RDError::TRANSPORT(e)) => Err(RDError::TRANSPORT(e)),
+            // With own client secret, token is refused until user approve device.
+            Err(_) if self.client_secret.is_some() => Ok(None),
+            Err(_) => Err(RDError::AUTH_FAILED),
+        }
+    }
+
+    fn emit(&self, event: DeviceAuthEvent) {
+        let _ = self.events.send(event);
+    }
+
+}
@@ -0,0 +1,102 @@
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::data_struct::RDError;
+use crate::downloader::DownloadFile;
+use crate::state_file;
+
+/// State of a partial download, saved next to the file.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct Journal {
+    /// File with last generated link used.
+    pub(crate) file: DownloadFile,
+    pub(crate) size: u64,
+    pub(crate) etag: Option<String>,
+    pub(crate) last_modified: Option<String>,
+    /// Written byte ranges, end excluded, sorted and merged.
+    done: Vec<(u64, u64)>,
+}
+
+impl Journal {
+
+    pub(crate) fn new(file: DownloadFile, size: u64) -> Journal {
+        Journal { file, size, etag: None, last_modified: None, done: Vec::new() }
+    }
+
+    /// Sidecar path of a downloaded file.
+    pub(crate) fn path(path: &Path) -> PathBuf {
+        let mut journal = path.as_os_str().to_owned();
+        journal.push(".journal");
+        PathBuf::from(journal)
+    }
+
+    /// Load journal, None if missing or unreadable.
+    pub(crate) async fn load(path: &Path) -> Option<Journal> {
+        state_file::load(path).await.ok().flatten()
+    }
+
+    pub(crate) async fn save(&self, path: &Path) -> Result<(), RDError> {
+        state_file::write_atomic(path, serde_json::to_vec(self)?).await
+    }
+
+    /// Check if journal is about the same file.
+    pub(crate) fn matches(&self, file: &DownloadFile) -> bool {
+        if file.link.is_empty() {
+            self.file.url == file.url
+        }
+        else {
+            self.file.link == file.link
+        }
+    }
+
+    /// Value for If-Range, weak etags are not allowed there.
+    pub(crate) fn validator(&self) -> Option<&str> {
+        self.etag.as_deref().filter(|etag| !etag.starts_with("W/")).or(self.last_modified.as_deref())
+    }
+
+    /// Mark range as written.
+    pub(crate) fn add(&mut self, start: u64, end: u64) {
+        if start >= end {
+            return;
+        }
+        self.done.push((start, end));
+        self.done.sort_unstable();
+
+        let mut merged: Vec<(u64, u64)> = Vec::with_capacity(self.done.len());
+        for &(start, end) in &self.done {
+            match merged.last_mut() {
+                Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+        self.done = merged;
+    }
+
+    /// Bytes already written.
+    pub(crate) fn done_bytes(&self) -> u64 {
+        self.done.iter().map(|(start, end)| end - start).sum()
+    }
+
+    /// Ranges still to download.
+    pub(crate) fn missing(&self) -> Vec<(u64, u64)> {
+        let mut missing = Vec::new();
+        let mut position = 0;
+        for &(start, end) in &self.done {
+            if start > position {
+                missing.push((position, start));
+            }
+            position = position.max(end);
+        }
+        if position < self.size {
+            missing.push((position, self.size));
+        }
+        missing
+    }
+
+    /// Forget written ranges and validators.
+    pub(crate) fn reset(&mut self) {
+        self.done.clear();
+        self.etag = None;
+        self.last_modified = None;
+    }
+
+}
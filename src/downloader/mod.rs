@@ -0,0 +1,384 @@
+pub(crate) mod journal;
+pub mod queue;
+
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use futures::future::try_join_all;
+use getset::Getters;
+use reqwest::{Response, StatusCode};
+use reqwest::header::{HeaderValue, CONTENT_LENGTH, ETAG, IF_RANGE, LAST_MODIFIED, RANGE};
+use serde::{Deserialize, Serialize};
+use tokio::fs::{self, OpenOptions};
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+use tokio::sync::{mpsc, Mutex};
+use crate::{RDClient, RDTraitAsync};
+use crate::bandwidth::BandwidthLimiter;
+use crate::downloader::journal::Journal;
+use crate::data_struct::RDError;
+use crate::data_struct::download::Download;
+use crate::data_struct::unrestrict::Unrestrict;
+
+/// Chunks smaller than 4 MiB are not worth a connection.
+pub const DEFAULT_MIN_CHUNK_SIZE: u64 = 4 * 1024 * 1024;
+/// Journal is saved each time a chunk wrote this many bytes.
+const SAVE_INTERVAL: u64 = 8 * 1024 * 1024;
+
+/// Generated link to download.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Getters)]
+pub struct DownloadFile {
+    /// Generated link.
+    #[getset(get = "pub")]
+    url: String,
+    /// Original link, empty when unknown.
+    #[getset(get = "pub")]
+    link: String,
+    #[getset(get = "pub")]
+    filename: String,
+    /// Bytes, 0 if unknown.
+    #[getset(get = "pub")]
+    filesize: u64,
+    /// Max chunks allowed.
+    #[getset(get = "pub")]
+    chunks: u32,
+}
+
+impl DownloadFile {
+
+    /// File from a generated link only, downloaded with one connection.
+    pub fn from_url(url: String) -> DownloadFile {
+        DownloadFile { url, chunks: 1, ..Default::default() }
+    }
+
+}
+
+impl From<Unrestrict> for DownloadFile {
+    fn from(d: Unrestrict) -> Self {
+        DownloadFile::from(&d)
+    }
+}
+
+impl From<&Unrestrict> for DownloadFile {
+    fn from(d: &Unrestrict) -> Self {
+        DownloadFile { url: d.download().clone(), link: d.link().clone(), filename: d.filename().clone(), filesize: *d.filesize(), chunks: *d.chunks() }
+    }
+}
+
+impl From<Download> for DownloadFile {
+    fn from(d: Download) -> Self {
+        DownloadFile::from(&d)
+    }
+}
+
+impl From<&Download> for DownloadFile {
+    fn from(d: &Download) -> Self {
+        DownloadFile { url: d.download().clone(), link: d.link().clone(), filename: d.filename().clone(), filesize: *d.filesize(), chunks: *d.chunks() }
+    }
+}
+
+/// Bytes written of a download.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DownloadProgress {
+    pub downloaded: u64,
+    /// Bytes, 0 if unknown.
+    pub total: u64,
+}
+
+/// Download generated links with parallel range requests.
+/// Partial downloads are resumed from a journal saved next to the file.
+#[derive(Debug, Clone)]
+pub struct Downloader {
+    client: RDClient,
+    max_chunks: Option<u32>,
+    min_chunk_size: u64,
+    resume: bool,
+    progress: Option<mpsc::UnboundedSender<DownloadProgress>>,
+    /// Limits of this downloader, in addition of client limit.
+    bandwidth: Vec<BandwidthLimiter>,
+}
+
+/// Byte range of file, end excluded.
+#[derive(Debug, Clone, Copy)]
+struct Chunk {
+    start: u64,
+    end: u64,
+}
+
+/// State shared by chunks of a download.
+struct Transfer<'a> {
+    path: &'a Path,
+    /// None when journal is not saved.
+    journal_path: Option<PathBuf>,
+    journal: Mutex<Journal>,
+    downloaded: AtomicU64,
+}
+
+impl Downloader {
+
+    pub fn new(client: RDClient) -> Downloader {
+        Downloader { client, max_chunks: None, min_chunk_size: DEFAULT_MIN_CHUNK_SIZE, resume: true, progress: None, bandwidth: Vec::new() }
+    }
+
+    /// Limit connections below chunks allowed by Real-Debrid.
+    pub fn max_chunks(mut self, max_chunks: u32) -> Self {
+        self.max_chunks = Some(max_chunks.max(1));
+        self
+    }
+
+    /// Minimal size of a chunk.
+    pub fn min_chunk_size(mut self, min_chunk_size: u64) -> Self {
+        self.min_chunk_size = min_chunk_size.max(1);
+        self
+    }
+
+    /// Save a journal to resume interrupted downloads (default : true).
+    pub fn resume(mut self, resume: bool) -> Self {
+        self.resume = resume;
+        self
+    }
+
+    /// Limit bandwidth of downloads of this downloader and its clones, in addition of other limits.
+    pub fn bandwidth_limit(mut self, limiter: BandwidthLimiter) -> Self {
+        self.bandwidth.push(limiter);
+        self
+    }
+
+    /// Send progress after each write.
+    pub fn progress(mut self, progress: mpsc::UnboundedSender<DownloadProgress>) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Download file to path and check its size, resuming a previous download of same file.
+    /// Expired generated link is replaced by unrestricting original link again.
+    /// Return size of file.
+    pub async fn download(&self, file: impl Into<DownloadFile>, path: impl AsRef<Path>) -> Result<u64, RDError> {
+        let file = file.into();
+        let path = path.as_ref();
+        let journal_path = Journal::path(path);
+        let allowed = file.chunks;
+
+        let journal = match self.resume_journal(&file, path, &journal_path).await {
+            Some(journal) => journal,
+            None => {
+                let size = match file.filesize {
+                    0 => self.probe_size(&file.url).await?,
+                    size => size,
+                };
+                let output = OpenOptions::new().create(true).write(true).truncate(true).open(path).await?;
+                output.set_len(size).await?;
+                Journal::new(file, size)
+            },
+        };
+        let mut size = journal.size;
+
+        // Without size, ranges can not be asked again.
+        let journal_path = (self.resume && size != 0).then_some(journal_path);
+        if let Some(journal_path) = &journal_path {
+            journal.save(journal_path).await?;
+        }
+        let transfer = Transfer { path, journal_path, downloaded: AtomicU64::new(journal.done_bytes()), journal: Mutex::new(journal) };
+
+        let mut restarted = false;
+        loop {
+            let chunks = self.split(&*transfer.journal.lock().await, allowed);
+            match try_join_all(chunks.iter().map(|chunk| self.fetch_chunk(&transfer, *chunk))).await {
+                Ok(_) => break,
+                // Remote file is not the one partially written, start again once.
+                Err(RDError::FILE_CHANGED) if !restarted => {
+                    restarted = true;
+                    let mut journal = transfer.journal.lock().await;
+                    journal.reset();
+                    // New file may have another size.
+                    size = self.probe_size(&journal.file.url).await?;
+                    journal.size = size;
+                    journal.file.filesize = size;
+                    OpenOptions::new().write(true).open(path).await?.set_len(size).await?;
+                    transfer.downloaded.store(0, Ordering::Relaxed);
+                    if let Some(journal_path) = &transfer.journal_path {
+                        journal.save(journal_path).await?;
+                    }
+                },
+                Err(e) => return Err(e),
+            }
+        }
+
+        let actual = fs::metadata(path).await?.len();
+        let written = transfer.journal.lock().await.done_bytes();
+        if size != 0 && (actual != size || written != size) {
+            return Err(RDError::SIZE_MISMATCH { expected: size, actual: written });
+        }
+        if let Some(journal_path) = &transfer.journal_path {
+            fs::remove_file(journal_path).await?;
+        }
+        Ok(actual)
+    }
+
+    /// Journal of a previous download of same file, when partial file is still there.
+    /// Generated link of file replaces the one of journal, which may be expired.
+    async fn resume_journal(&self, file: &DownloadFile, path: &Path, journal_path: &Path) -> Option<Journal> {
+        if !self.resume {
+            return None;
+        }
+        let mut journal = Journal::load(journal_path).await?;
+        let partial = fs::metadata(path).await.ok()?.len();
+        let same_size = file.filesize == 0 || file.filesize == journal.size;
+        if !(journal.matches(file) && same_size && partial == journal.size) {
+            return None;
+        }
+        journal.file.url = file.url.clone();
+        Some(journal)
+    }
+
+    /// Size from Content-Length of a HEAD request, 0 if unknown.
+    pub(crate) async fn probe_size(&self, url: &str) -> Result<u64, RDError> {
+        let response = self.client.client.head(url).send().await?;
+        if !response.status().is_success() {
+            return Err(RDError::from_status(response.status()));
+        }
+        Ok(response.headers().get(CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(0))
+    }
+
+    /// Split missing ranges until allowed chunks are used, one chunk when size is unknown.
+    fn split(&self, journal: &Journal, allowed: u32) -> Vec<Chunk> {
+        if journal.size == 0 {
+            return vec![Chunk { start: 0, end: 0 }];
+        }
+        let allowed = self.max_chunks.map_or(allowed, |max| max.min(allowed)).max(1) as usize;
+
+        let mut chunks = journal.missing().into_iter().map(|(start, end)| Chunk { start, end }).collect::<Vec<_>>();
+        while chunks.len() < allowed {
+            let Some((index, largest)) = chunks.iter().enumerate().max_by_key(|(_, chunk)| chunk.end - chunk.start) else {
+                break;
+            };
+            let half = (largest.end - largest.start) / 2;
+            if half < self.min_chunk_size {
+                break;
+            }
+            let middle = largest.start + half;
+            let end = largest.end;
+            chunks[index].end = middle;
+            chunks.push(Chunk { start: middle, end });
+        }
+        chunks
+    }
+
+    /// Download a chunk and write it at its place.
+    async fn fetch_chunk(&self, transfer: &Transfer<'_>, chunk: Chunk) -> Result<(), RDError> {
+        let size = transfer.journal.lock().await.size;
+        let whole = chunk.start == 0 && chunk.end == size;
+        let mut renewed = false;
+
+        let mut response = loop {
+            let (url, validator) = {
+                let journal = transfer.journal.lock().await;
+                (journal.file.url.clone(), journal.validator().map(str::to_string))
+            };
+
+            let mut request = self.client.client.get(url.as_str());
+            if size != 0 {
+                request = request.header(RANGE, format!("bytes={}-{}", chunk.start, chunk.end - 1));
+                if let Some(validator) = &validator {
+                    request = request.header(IF_RANGE, validator.as_str());
+                }
+            }
+
+            let response = request.send().await?;
+            match response.status() {
+                StatusCode::PARTIAL_CONTENT => break response,
+                // Server ignored range, only usable when whole file is asked.
+                StatusCode::OK if whole => break response,
+                // If-Range failed, file changed since journal was saved.
+                StatusCode::OK if validator.is_some() => return Err(RDError::FILE_CHANGED),
+                StatusCode::FORBIDDEN | StatusCode::NOT_FOUND | StatusCode::GONE if !renewed => {
+                    self.renew(transfer, &url, response.status()).await?;
+                    renewed = true;
+                },
+                status => return Err(RDError::from_status(status)),
+            }
+        };
+        Self::keep_validator(transfer, &response).await;
+
+        let mut output = OpenOptions::new().write(true).open(transfer.path).await?;
+        output.seek(SeekFrom::Start(chunk.start)).await?;
+
+        let mut position = chunk.start;
+        let mut saved = chunk.start;
+        while let Some(bytes) = response.chunk().await? {
+            for limiter in self.client.bandwidth.iter().chain(&self.bandwidth) {
+                limiter.acquire(bytes.len() as u64).await;
+            }
+            output.write_all(&bytes).await?;
+            position += bytes.len() as u64;
+
+            let total = transfer.downloaded.fetch_add(bytes.len() as u64, Ordering::Relaxed) + bytes.len() as u64;
+            if let Some(progress) = &self.progress {
+                let _ = progress.send(DownloadProgress { downloaded: total, total: size });
+            }
+
+            if position - saved >= SAVE_INTERVAL {
+                output.flush().await?;
+                Self::mark(transfer, saved, position).await?;
+                saved = position;
+            }
+        }
+        output.flush().await?;
+        Self::mark(transfer, saved, position).await?;
+
+        if size != 0 && position != chunk.end {
+            return Err(RDError::SIZE_MISMATCH { expected: chunk.end - chunk.start, actual: position - chunk.start });
+        }
+        Ok(())
+    }
+
+    /// Replace expired generated link, unless another chunk already did.
+    async fn renew(&self, transfer: &Transfer<'_>, used_url: &str, status: StatusCode) -> Result<(), RDError> {
+        let mut journal = transfer.journal.lock().await;
+        if journal.file.url != used_url {
+            return Ok(());
+        }
+        if journal.file.link.is_empty() {
+            return Err(RDError::from_status(status));
+        }
+
+        let unrestrict = self.client.unrestrict_link(journal.file.link.clone(), None, None).await?;
+        journal.file.url = unrestrict.download().clone();
+        if let Some(journal_path) = &transfer.journal_path {
+            journal.save(journal_path).await?;
+        }
+        Ok(())
+    }
+
+    /// Keep ETag or Last-Modified of first response to check file on resume.
+    async fn keep_validator(transfer: &Transfer<'_>, response: &Response) {
+        let mut journal = transfer.journal.lock().await;
+        if journal.etag.is_none() && journal.last_modified.is_none() {
+            let header = |name| response.headers().get(name).and_then(|value: &HeaderValue| value.to_str().ok()).map(str::to_string);
+            journal.etag = header(ETAG);
+            journal.last_modified = header(LAST_MODIFIED);
+        }
+    }
+
+    /// Mark flushed range as written and save journal.
+    async fn mark(transfer: &Transfer<'_>, start: u64, end: u64) -> Result<(), RDError> {
+        let mut journal = transfer.journal.lock().await;
+        journal.add(start, end);
+        if let Some(journal_path) = &transfer.journal_path {
+            journal.save(journal_path).await?;
+        }
+        Ok(())
+    }
+
+}
+
+impl RDClient {
+
+    /// Create downloader for generated links.
+    pub fn downloader(&self) -> Downloader {
+        Downloader::new(self.clone())
+    }
+
+}
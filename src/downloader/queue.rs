@@ -0,0 +1,557 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use chrono::{Local, Timelike};
+use getset::Getters;
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use tokio::sync::{broadcast, mpsc, Notify};
+use tokio_util::sync::CancellationToken;
+use crate::RDTraitAsync;
+use crate::bandwidth::BandwidthLimiter;
+use crate::data_struct::RDError;
+use crate::data_struct::download::Download;
+use crate::data_struct::torrent::Torrent;
+use crate::data_struct::unrestrict::Unrestrict;
+use crate::downloader::{DownloadFile, DownloadProgress, Downloader};
+use crate::downloader::journal::Journal;
+use crate::state_file::StateFile;
+
+pub const DEFAULT_CONCURRENCY: usize = 2;
+/// Schedule is checked at least this often.
+const SCHEDULE_TICK: Duration = Duration::from_secs(30);
+
+/// What a job downloads.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[allow(non_camel_case_types)]
+pub enum JobSource {
+    /// Generated link.
+    FILE(DownloadFile),
+    /// Hoster link, unrestricted when job start.
+    LINK(String),
+}
+
+impl From<DownloadFile> for JobSource {
+    fn from(d: DownloadFile) -> Self {
+        JobSource::FILE(d)
+    }
+}
+
+impl From<Unrestrict> for JobSource {
+    fn from(d: Unrestrict) -> Self {
+        JobSource::FILE(DownloadFile::from(d))
+    }
+}
+
+impl From<Download> for JobSource {
+    fn from(d: Download) -> Self {
+        JobSource::FILE(DownloadFile::from(d))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum JobStatus {
+    QUEUED,
+    RUNNING,
+    PAUSED,
+    COMPLETED,
+    FAILED,
+    CANCELLED,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Getters)]
+pub struct Job {
+    #[getset(get = "pub")]
+    id: u64,
+    #[getset(get = "pub")]
+    source: JobSource,
+    /// Directory of downloaded file.
+    #[getset(get = "pub")]
+    directory: PathBuf,
+    /// Path of downloaded file, known once job started.
+    #[getset(get = "pub")]
+    path: Option<PathBuf>,
+    /// Higher priority start first.
+    #[getset(get = "pub")]
+    priority: i32,
+    #[getset(get = "pub")]
+    status: JobStatus,
+    /// Message of last error.
+    #[getset(get = "pub")]
+    error: Option<String>,
+    /// Bytes per second, None for no limit.
+    #[getset(get = "pub")]
+    #[serde(default)]
+    max_speed: Option<u64>,
+}
+
+/// Daily window of local time, crossing midnight when end is before start.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeWindow {
+    /// Minutes since midnight.
+    start: u32,
+    end: u32,
+}
+
+impl TimeWindow {
+
+    /// Window from start to end, as (hour, minute), end may be (24, 0).
+    /// INVALID_TIME_WINDOW when a time is out of day or start equals end.
+    pub fn new(start: (u32, u32), end: (u32, u32)) -> Result<TimeWindow, RDError> {
+        let minutes = |(hour, minute): (u32, u32)| (hour < 24 && minute < 60).then_some(hour * 60 + minute);
+        let start = minutes(start).ok_or(RDError::INVALID_TIME_WINDOW)?;
+        let end = if end == (24, 0) {
+            24 * 60
+        }
+        else {
+            minutes(end).ok_or(RDError::INVALID_TIME_WINDOW)?
+        };
+        if start == end {
+            return Err(RDError::INVALID_TIME_WINDOW);
+        }
+        Ok(TimeWindow { start, end })
+    }
+
+    /// Check if minutes since midnight are in window.
+    pub fn contains(&self, minutes: u32) -> bool {
+        if self.start <= self.end {
+            self.start <= minutes && minutes < self.end
+        }
+        else {
+            minutes >= self.start || minutes < self.end
+        }
+    }
+
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum QueueEvent {
+    ADDED(u64),
+    STARTED(u64),
+    PROGRESS(u64, DownloadProgress),
+    PAUSED(u64),
+    RESUMED(u64),
+    /// Stopped by schedule or shutdown, started again later.
+    REQUEUED(u64),
+    COMPLETED(u64),
+    FAILED(u64, String),
+    CANCELLED(u64),
+}
+
+/// State saved on disk.
+#[derive(Serialize, Deserialize, Debug)]
+struct QueueState {
+    next_id: u64,
+    concurrency: usize,
+    windows: Vec<TimeWindow>,
+    /// Bytes per second of all jobs, None for no limit.
+    #[serde(default)]
+    bandwidth: Option<u64>,
+    jobs: Vec<Job>,
+}
+
+impl Default for QueueState {
+    fn default() -> Self {
+        QueueState { next_id: 1, concurrency: DEFAULT_CONCURRENCY, windows: Vec::new(), bandwidth: None, jobs: Vec::new() }
+    }
+}
+
+#[derive(Debug)]
+struct Inner {
+    downloader: Downloader,
+    state: StateFile<QueueState>,
+    /// Cancel token of running jobs.
+    running: Mutex<HashMap<u64, CancellationToken>>,
+    /// Limit shared by all jobs.
+    bandwidth: BandwidthLimiter,
+    /// Limit of running jobs.
+    job_bandwidth: Mutex<HashMap<u64, BandwidthLimiter>>,
+    events: broadcast::Sender<QueueEvent>,
+    wake: Notify,
+    shutdown: CancellationToken,
+}
+
+/// Queue of downloads saved on disk, run by run until shutdown.
+/// Clones share the same queue.
+#[derive(Debug, Clone)]
+pub struct DownloadQueue {
+    inner: Arc<Inner>,
+}
+
+impl DownloadQueue {
+
+    /// Open queue saved at path, or a new empty queue.
+    /// Jobs running when queue was stopped are queued again.
+    pub async fn open(downloader: Downloader, path: impl AsRef<Path>) -> Result<DownloadQueue, RDError> {
+        let state = StateFile::<QueueState>::open(path.as_ref()).await?;
+        let bandwidth = {
+            let mut state = state.lock();
+            state.jobs.iter_mut().filter(|job| job.status == JobStatus::RUNNING).for_each(|job| job.status = JobStatus::QUEUED);
+            state.bandwidth
+        };
+
+        let (events, _) = broadcast::channel(256);
+        Ok(DownloadQueue { inner: Arc::new(Inner {
+            downloader,
+            bandwidth: BandwidthLimiter::with_rate(bandwidth),
+            state,
+            running: Mutex::new(HashMap::new()),
+            job_bandwidth: Mutex::new(HashMap::new()),
+            events,
+            wake: Notify::new(),
+            shutdown: CancellationToken::new(),
+        }) })
+    }
+
+    /// Add a job downloading in directory.
+    pub async fn add(&self, source: impl Into<JobSource>, directory: impl AsRef<Path>, priority: i32) -> Result<u64, RDError> {
+        let id = {
+            let mut state = self.state();
+            let id = state.next_id;
+            state.next_id += 1;
+            state.jobs.push(Job { id, source: source.into(), directory: directory.as_ref().to_path_buf(), path: None, priority, status: JobStatus::QUEUED, error: None, max_speed: None });
+            id
+        };
+        self.changed(QueueEvent::ADDED(id)).await?;
+        Ok(id)
+    }
+
+    /// Add a job for each link of torrent.
+    pub async fn add_torrent(&self, torrent: &Torrent, directory: impl AsRef<Path>, priority: i32) -> Result<Vec<u64>, RDError> {
+        let mut ids = Vec::with_capacity(torrent.links().len());
+        for link in torrent.links() {
+            ids.push(self.add(JobSource::LINK(link.clone()), directory.as_ref(), priority).await?);
+        }
+        Ok(ids)
+    }
+
+    /// Stop a job, partial file is kept to resume it later.
+    pub async fn pause(&self, id: u64) -> Result<(), RDError> {
+        self.update(id, |job| match job.status {
+            JobStatus::QUEUED | JobStatus::RUNNING => {
+                job.status = JobStatus::PAUSED;
+                Ok(())
+            },
+            _ => Err(RDError::ACTION_ALREADY_DONE),
+        })?;
+        self.stop(id);
+        self.changed(QueueEvent::PAUSED(id)).await
+    }
+
+    /// Queue again a paused or failed job.
+    pub async fn resume(&self, id: u64) -> Result<(), RDError> {
+        self.update(id, |job| match job.status {
+            JobStatus::PAUSED | JobStatus::FAILED => {
+                job.status = JobStatus::QUEUED;
+                job.error = None;
+                Ok(())
+            },
+            _ => Err(RDError::ACTION_ALREADY_DONE),
+        })?;
+        self.changed(QueueEvent::RESUMED(id)).await
+    }
+
+    /// Stop a job and delete its partial file.
+    pub async fn cancel(&self, id: u64) -> Result<(), RDError> {
+        let path = self.update(id, |job| match job.status {
+            JobStatus::COMPLETED | JobStatus::CANCELLED => Err(RDError::ACTION_ALREADY_DONE),
+            _ => {
+                job.status = JobStatus::CANCELLED;
+                Ok(job.path.clone())
+            },
+        })?;
+        // Running job delete its file once stopped.
+        if !self.stop(id) {
+            if let Some(path) = path {
+                Self::remove_partial(&path).await;
+            }
+        }
+        self.changed(QueueEvent::CANCELLED(id)).await
+    }
+
+    pub async fn set_priority(&self, id: u64, priority: i32) -> Result<(), RDError> {
+        self.update(id, |job| {
+            job.priority = priority;
+            Ok(())
+        })?;
+        self.save().await
+    }
+
+    /// Limit bandwidth of a job, applied at once when job is running.
+    pub async fn set_job_bandwidth(&self, id: u64, bytes_per_second: Option<u64>) -> Result<(), RDError> {
+        self.update(id, |job| {
+            job.max_speed = bytes_per_second;
+            Ok(())
+        })?;
+        if let Some(limiter) = self.job_bandwidth().get(&id) {
+            limiter.set_rate(bytes_per_second);
+        }
+        self.save().await
+    }
+
+    /// Limit bandwidth of all jobs together, applied at once to running jobs.
+    pub async fn set_bandwidth(&self, bytes_per_second: Option<u64>) -> Result<(), RDError> {
+        self.state().bandwidth = bytes_per_second;
+        self.inner.bandwidth.set_rate(bytes_per_second);
+        self.save().await
+    }
+
+    /// Jobs downloading at once.
+    pub async fn set_concurrency(&self, concurrency: usize) -> Result<(), RDError> {
+        self.state().concurrency = concurrency.max(1);
+        self.save().await?;
+        self.inner.wake.notify_one();
+        Ok(())
+    }
+
+    /// Jobs only run inside these windows, running jobs are stopped outside.
+    /// Empty for no restriction.
+    pub async fn set_windows(&self, windows: Vec<TimeWindow>) -> Result<(), RDError> {
+        self.state().windows = windows;
+        self.save().await?;
+        self.inner.wake.notify_one();
+        Ok(())
+    }
+
+    /// Forget completed and cancelled jobs.
+    pub async fn clear_finished(&self) -> Result<(), RDError> {
+        self.state().jobs.retain(|job| !matches!(job.status, JobStatus::COMPLETED | JobStatus::CANCELLED));
+        self.save().await
+    }
+
+    pub fn jobs(&self) -> Vec<Job> {
+        self.state().jobs.clone()
+    }
+
+    pub fn job(&self, id: u64) -> Option<Job> {
+        self.state().jobs.iter().find(|job| job.id == id).cloned()
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<QueueEvent> {
+        self.inner.events.subscribe()
+    }
+
+    /// Start jobs until shutdown is called.
+    pub async fn run(&self) {
+        loop {
+            if self.inner.shutdown.is_cancelled() {
+                self.requeue_running().await;
+                return;
+            }
+
+            if self.in_window() {
+                self.start_jobs().await;
+            }
+            else {
+                self.requeue_running().await;
+            }
+
+            tokio::select! {
+                _ = self.inner.wake.notified() => {},
+                _ = tokio::time::sleep(SCHEDULE_TICK) => {},
+                _ = self.inner.shutdown.cancelled() => {},
+            }
+        }
+    }
+
+    /// Stop run and running jobs, they are started again by next run.
+    pub fn shutdown(&self) {
+        self.inner.shutdown.cancel();
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, QueueState> {
+        self.inner.state.lock()
+    }
+
+    fn running(&self) -> std::sync::MutexGuard<'_, HashMap<u64, CancellationToken>> {
+        self.inner.running.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn job_bandwidth(&self) -> std::sync::MutexGuard<'_, HashMap<u64, BandwidthLimiter>> {
+        self.inner.job_bandwidth.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn update<T>(&self, id: u64, update: impl FnOnce(&mut Job) -> Result<T, RDError>) -> Result<T, RDError> {
+        let mut state = self.state();
+        let job = state.jobs.iter_mut().find(|job| job.id == id).ok_or(RDError::UNKNOWN_RESSOURCE)?;
+        update(job)
+    }
+
+    /// Cancel job task, false if job is not running.
+    fn stop(&self, id: u64) -> bool {
+        match self.running().get(&id) {
+            Some(token) => {
+                token.cancel();
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn in_window(&self) -> bool {
+        let state = self.state();
+        let now = Local::now();
+        let minutes = now.hour() * 60 + now.minute();
+        state.windows.is_empty() || state.windows.iter().any(|window| window.contains(minutes))
+    }
+
+    fn emit(&self, event: QueueEvent) {
+        let _ = self.inner.events.send(event);
+    }
+
+    /// Emit event, save state and wake run.
+    async fn changed(&self, event: QueueEvent) -> Result<(), RDError> {
+        self.emit(event);
+        self.save().await?;
+        self.inner.wake.notify_one();
+        Ok(())
+    }
+
+    async fn save(&self) -> Result<(), RDError> {
+        self.inner.state.save().await
+    }
+
+    /// Start queued jobs by priority while concurrency allow it.
+    async fn start_jobs(&self) {
+        loop {
+            let job = {
+                let mut state = self.state();
+                let mut running = self.running();
+                if running.len() >= state.concurrency {
+                    break;
+                }
+                let next = state.jobs.iter_mut()
+                    .filter(|job| job.status == JobStatus::QUEUED && !running.contains_key(&job.id))
+                    .min_by_key(|job| (-(job.priority as i64), job.id));
+                let Some(job) = next else {
+                    break;
+                };
+                job.status = JobStatus::RUNNING;
+                job.error = None;
+                running.insert(job.id, CancellationToken::new());
+                self.job_bandwidth().insert(job.id, BandwidthLimiter::with_rate(job.max_speed));
+                job.clone()
+            };
+
+            let _ = self.changed(QueueEvent::STARTED(job.id)).await;
+            let token = self.running().get(&job.id).cloned().unwrap_or_default();
+            let queue = self.clone();
+            tokio::spawn(async move {
+                let id = job.id;
+                let result = tokio::select! {
+                    _ = token.cancelled() => None,
+                    result = queue.execute(job) => Some(result),
+                };
+                queue.finish(id, result).await;
+            });
+        }
+    }
+
+    /// Stop running jobs and queue them again.
+    async fn requeue_running(&self) {
+        let ids = self.running().keys().copied().collect::<Vec<_>>();
+        for id in ids {
+            let requeued = self.update(id, |job| {
+                let running = job.status == JobStatus::RUNNING;
+                if running {
+                    job.status = JobStatus::QUEUED;
+                }
+                Ok(running)
+            });
+            self.stop(id);
+            if let Ok(true) = requeued {
+                self.emit(QueueEvent::REQUEUED(id));
+            }
+        }
+        let _ = self.save().await;
+    }
+
+    /// Unrestrict link if needed and download file.
+    async fn execute(&self, job: Job) -> Result<(), RDError> {
+        let file = match job.source {
+            JobSource::FILE(file) => file,
+            JobSource::LINK(link) => {
+                let unrestrict = self.inner.downloader.client.unrestrict_link(link, None, None).await?;
+                let file = DownloadFile::from(unrestrict);
+                self.update(job.id, |job| {
+                    job.source = JobSource::FILE(file.clone());
+                    Ok(())
+                })?;
+                file
+            },
+        };
+
+        let path = match job.path {
+            Some(path) => path,
+            None => {
+                let filename = Path::new(&file.filename).file_name().map(PathBuf::from).unwrap_or_else(|| PathBuf::from(format!("download-{}", job.id)));
+                let path = job.directory.join(filename);
+                self.update(job.id, |job| {
+                    job.path = Some(path.clone());
+                    Ok(())
+                })?;
+                path
+            },
+        };
+        self.save().await?;
+        fs::create_dir_all(&job.directory).await?;
+
+        let (progress, mut receiver) = mpsc::unbounded_channel();
+        let events = self.inner.events.clone();
+        tokio::spawn(async move {
+            while let Some(progress) = receiver.recv().await {
+                let _ = events.send(QueueEvent::PROGRESS(job.id, progress));
+            }
+        });
+
+        let mut downloader = self.inner.downloader.clone().progress(progress).bandwidth_limit(self.inner.bandwidth.clone());
+        if let Some(limiter) = self.job_bandwidth().get(&job.id).cloned() {
+            downloader = downloader.bandwidth_limit(limiter);
+        }
+        downloader.download(file, &path).await?;
+        Ok(())
+    }
+
+    /// Record result of job task, None when task was stopped.
+    async fn finish(&self, id: u64, result: Option<Result<(), RDError>>) {
+        self.running().remove(&id);
+        self.job_bandwidth().remove(&id);
+
+        let outcome = self.update(id, |job| {
+            Ok(match result {
+                Some(Ok(())) => {
+                    job.status = JobStatus::COMPLETED;
+                    (Some(QueueEvent::COMPLETED(id)), None)
+                },
+                Some(Err(e)) => {
+                    job.status = JobStatus::FAILED;
+                    job.error = Some(e.to_string());
+                    (Some(QueueEvent::FAILED(id, e.to_string())), None)
+                },
+                None if job.status == JobStatus::CANCELLED => (None, job.path.clone()),
+                None => (None, None),
+            })
+        });
+
+        if let Ok((event, cancelled)) = outcome {
+            if let Some(path) = cancelled {
+                Self::remove_partial(&path).await;
+            }
+            if let Some(event) = event {
+                self.emit(event);
+            }
+        }
+        let _ = self.save().await;
+        self.inner.wake.notify_one();
+    }
+
+    /// Delete partial file and its journal.
+    async fn remove_partial(path: &Path) {
+        let _ = fs::remove_file(path).await;
+        let _ = fs::remove_file(Journal::path(path)).await;
+    }
+
+}
@@ -0,0 +1,272 @@
+use std::sync::LazyLock;
+use std::time::Duration;
+use futures::StreamExt;
+use regex::Regex;
+use crate::{RDClient, RDTraitAsync};
+use crate::data_struct::RDError;
+use crate::data_struct::torrent::{ParamsTorrent, ParamsTorrentFile, TorrentFile, TorrentStatus};
+use crate::torrent_watcher::TorrentEvent;
+
+/// Dead magnets stay in conversion forever.
+pub const DEFAULT_CONVERSION_TIMEOUT: Duration = Duration::from_secs(300);
+/// Longest interval between polls while magnet is converted.
+const CONVERSION_MAX_INTERVAL: Duration = Duration::from_secs(10);
+pub const VIDEO_EXTENSIONS: &[&str] = &["mkv", "mp4", "avi", "m4v", "mov", "wmv", "ts", "m2ts", "webm", "mpg", "mpeg"];
+/// Folders and names of bonus content.
+const EXTRAS: &[&str] = &["sample", "samples", "extras", "extra", "featurettes", "featurette", "trailer", "trailers", "behind the scenes", "deleted scenes", "bonus"];
+/// S01E02, s01.e02 or 1x02.
+static EPISODE_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?:^|[^a-z0-9])(?:s(\d{1,3})[ ._-]?e(\d{1,4})|(\d{1,2})x(\d{1,3}))(?:[^0-9]|$)").expect("valid episode pattern")
+});
+
+/// Rule deciding if a file is selected.
+#[derive(Debug, Clone)]
+#[allow(non_camel_case_types)]
+pub enum FileRule {
+    /// Extension in list, without dot and case insensitive.
+    EXTENSIONS(Vec<String>),
+    EXCLUDE_EXTENSIONS(Vec<String>),
+    /// Path matches regex.
+    REGEX(Regex),
+    MIN_SIZE(u64),
+    MAX_SIZE(u64),
+    /// No folder or file name like sample, extras, trailer...
+    NO_EXTRAS,
+    /// Episode of season like S01E02 or 1x02, any episode of season when None.
+    EPISODE { season: u32, episode: Option<u32> },
+    /// Only the largest of files kept by other rules, in rules of selector or in ALL.
+    /// It does not match or reject files itself, so ANY and NOT ignore it.
+    LARGEST_ONLY,
+    ALL(Vec<FileRule>),
+    ANY(Vec<FileRule>),
+    NOT(Box<FileRule>),
+}
+
+impl FileRule {
+
+    /// Path matches glob, * and ? stop at /, ** match any folders.
+    pub fn glob(glob: &str) -> Result<FileRule, regex::Error> {
+        let mut pattern = String::from("(?i)^");
+        let mut chars = glob.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '*' if chars.peek() == Some(&'*') => {
+                    chars.next();
+                    // **/ also matches no folder.
+                    if chars.peek() == Some(&'/') {
+                        chars.next();
+                        pattern.push_str("(?:.*/)?");
+                    }
+                    else {
+                        pattern.push_str(".*");
+                    }
+                },
+                '*' => pattern.push_str("[^/]*"),
+                '?' => pattern.push_str("[^/]"),
+                c => pattern.push_str(regex::escape(c.to_string().as_str()).as_str()),
+            }
+        }
+        pattern.push('$');
+        Ok(FileRule::REGEX(Regex::new(&pattern)?))
+    }
+
+    /// Path matches regex.
+    pub fn regex(regex: &str) -> Result<FileRule, regex::Error> {
+        Ok(FileRule::REGEX(Regex::new(regex)?))
+    }
+
+    /// Check rule on one file.
+    pub fn matches(&self, file: &TorrentFile) -> bool {
+        self.check(file).unwrap_or(true)
+    }
+
+    /// Check rule on one file, None when rule only has LARGEST_ONLY.
+    fn check(&self, file: &TorrentFile) -> Option<bool> {
+        let path = file.path();
+        let checked = match self {
+            FileRule::EXTENSIONS(d) => extension(path).is_some_and(|ext| d.iter().any(|d| d.eq_ignore_ascii_case(ext))),
+            FileRule::EXCLUDE_EXTENSIONS(d) => !extension(path).is_some_and(|ext| d.iter().any(|d| d.eq_ignore_ascii_case(ext))),
+            FileRule::REGEX(d) => d.is_match(path),
+            FileRule::MIN_SIZE(d) => file.bytes() >= d,
+            FileRule::MAX_SIZE(d) => file.bytes() <= d,
+            FileRule::NO_EXTRAS => !path.to_lowercase().split('/').any(is_extra),
+            FileRule::EPISODE { season, episode } => matches_episode(path, *season, *episode),
+            FileRule::LARGEST_ONLY => return None,
+            FileRule::ALL(d) => {
+                let checked = d.iter().filter_map(|rule| rule.check(file)).collect::<Vec<_>>();
+                return (!checked.is_empty()).then(|| checked.into_iter().all(|d| d));
+            },
+            FileRule::ANY(d) => {
+                let checked = d.iter().filter_map(|rule| rule.check(file)).collect::<Vec<_>>();
+                return (!checked.is_empty()).then(|| checked.into_iter().any(|d| d));
+            },
+            FileRule::NOT(d) => return d.check(file).map(|d| !d),
+        };
+        Some(checked)
+    }
+
+    fn largest_only(&self) -> bool {
+        match self {
+            FileRule::LARGEST_ONLY => true,
+            FileRule::ALL(d) => d.iter().any(FileRule::largest_only),
+            _ => false,
+        }
+    }
+
+}
+
+/// Set of rules which must all match.
+#[derive(Debug, Clone)]
+pub struct FileSelector {
+    rules: Vec<FileRule>,
+    timeout: Duration,
+}
+
+impl Default for FileSelector {
+    fn default() -> Self {
+        FileSelector { rules: Vec::new(), timeout: DEFAULT_CONVERSION_TIMEOUT }
+    }
+}
+
+impl FileSelector {
+
+    /// Selector keeping every file.
+    pub fn new() -> FileSelector {
+        FileSelector::default()
+    }
+
+    /// Video files without samples and extras.
+    pub fn video() -> FileSelector {
+        FileSelector::new().extensions(VIDEO_EXTENSIONS).no_extras()
+    }
+
+    pub fn rule(mut self, rule: FileRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    pub fn extensions(self, extensions: &[&str]) -> Self {
+        self.rule(FileRule::EXTENSIONS(extensions.iter().map(|d| d.to_string()).collect()))
+    }
+
+    pub fn exclude_extensions(self, extensions: &[&str]) -> Self {
+        self.rule(FileRule::EXCLUDE_EXTENSIONS(extensions.iter().map(|d| d.to_string()).collect()))
+    }
+
+    pub fn glob(self, glob: &str) -> Result<Self, regex::Error> {
+        Ok(self.rule(FileRule::glob(glob)?))
+    }
+
+    pub fn exclude_glob(self, glob: &str) -> Result<Self, regex::Error> {
+        Ok(self.rule(FileRule::NOT(Box::new(FileRule::glob(glob)?))))
+    }
+
+    pub fn regex(self, regex: &str) -> Result<Self, regex::Error> {
+        Ok(self.rule(FileRule::regex(regex)?))
+    }
+
+    pub fn exclude_regex(self, regex: &str) -> Result<Self, regex::Error> {
+        Ok(self.rule(FileRule::NOT(Box::new(FileRule::regex(regex)?))))
+    }
+
+    pub fn min_size(self, bytes: u64) -> Self {
+        self.rule(FileRule::MIN_SIZE(bytes))
+    }
+
+    pub fn max_size(self, bytes: u64) -> Self {
+        self.rule(FileRule::MAX_SIZE(bytes))
+    }
+
+    pub fn no_extras(self) -> Self {
+        self.rule(FileRule::NO_EXTRAS)
+    }
+
+    pub fn episode(self, season: u32, episode: Option<u32>) -> Self {
+        self.rule(FileRule::EPISODE { season, episode })
+    }
+
+    pub fn largest_only(self) -> Self {
+        self.rule(FileRule::LARGEST_ONLY)
+    }
+
+    /// Time select_torrent_files_with waits for end of magnet conversion (default : 5min).
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Files matching all rules.
+    pub fn select<'a>(&self, files: &'a [TorrentFile]) -> Vec<&'a TorrentFile> {
+        let selected = files.iter().filter(|file| self.rules.iter().all(|rule| rule.matches(file)));
+        if self.rules.iter().any(FileRule::largest_only) {
+            selected.max_by_key(|file| *file.bytes()).into_iter().collect()
+        }
+        else {
+            selected.collect()
+        }
+    }
+
+}
+
+fn extension(path: &str) -> Option<&str> {
+    let name = path.rsplit('/').next()?;
+    name.rsplit_once('.').map(|(_, ext)| ext).filter(|ext| !ext.is_empty())
+}
+
+/// Folder or file name (lowercase) of bonus content.
+fn is_extra(part: &str) -> bool {
+    let stem = part.rsplit_once('.').map_or(part, |(stem, _)| stem);
+    EXTRAS.contains(&stem) || stem.split(|c: char| !c.is_ascii_alphanumeric()).any(|word| word == "sample")
+}
+
+fn matches_episode(path: &str, season: u32, episode: Option<u32>) -> bool {
+    let name = path.rsplit('/').next().unwrap_or(path).to_lowercase();
+    EPISODE_PATTERN.captures_iter(&name).any(|captures| {
+        let number = |i: usize, j: usize| captures.get(i).or(captures.get(j)).and_then(|d| d.as_str().parse::<u32>().ok());
+        number(1, 3) == Some(season) && episode.is_none_or(|episode| number(2, 4) == Some(episode))
+    })
+}
+
+impl RDClient {
+
+    /// Wait until torrent waits files selection, then select files of selector.
+    /// Return selected files, NO_FILE_SELECTED when no file match and TIMEOUT when magnet is still converted after timeout of selector.
+    pub async fn select_torrent_files_with(&self, torrent: ParamsTorrent, selector: &FileSelector) -> Result<Vec<TorrentFile>, RDError> {
+        let id = match torrent {
+            ParamsTorrent::FROM_STRUCT(d) => d.id().to_string(),
+            ParamsTorrent::FROM_ADD(d) => d.id().to_string(),
+            ParamsTorrent::FROM_ID(d) => d,
+        };
+
+        tokio::time::timeout(selector.timeout, self.wait_files_selection(&id)).await.map_err(|_| RDError::TIMEOUT)??;
+        let torrent = self.get_torrents_info(ParamsTorrent::FROM_ID(id.clone())).await?;
+        let files = torrent.files().clone().unwrap_or_default();
+        let selected = selector.select(&files).into_iter().cloned().collect::<Vec<_>>();
+        if selected.is_empty() {
+            return Err(RDError::NO_FILE_SELECTED);
+        }
+
+        self.select_torrent_file(ParamsTorrent::FROM_ID(id), ParamsTorrentFile::FROM_STRUCTS(selected.clone())).await?;
+        Ok(selected)
+    }
+
+    /// Watch torrent until magnet is converted.
+    async fn wait_files_selection(&self, id: &str) -> Result<(), RDError> {
+        let mut events = Box::pin(self.torrent_watcher().watch(ParamsTorrent::FROM_ID(id.to_string())).max_interval(CONVERSION_MAX_INTERVAL).events());
+        while let Some(event) = events.next().await {
+            match event {
+                Ok(TorrentEvent::STATUS_CHANGED { new, .. }) => match new {
+                    TorrentStatus::WAITING_FILES_SELECTION => return Ok(()),
+                    TorrentStatus::MAGNET_CONVERSION => {},
+                    status if status.is_failed() => return Err(RDError::TORRENT_FAILED(status)),
+                    _ => return Err(RDError::ACTION_ALREADY_DONE),
+                },
+                Ok(_) => {},
+                Err(e) if e.is_transient() => {},
+                Err(e) => return Err(e),
+            }
+        }
+        Err(RDError::ACTION_ALREADY_DONE)
+    }
+
+}
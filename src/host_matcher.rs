@@ -0,0 +1,158 @@
+use std::sync::Arc;
+use std::time::Duration;
+use regex::{Regex, RegexSet};
+use reqwest::Url;
+use crate::{RDClient, RDTraitAsync};
+use crate::data_struct::RDError;
+use crate::ttl_cache::TtlCache;
+
+/// Supported hosts change rarely, patterns are fetched again after one hour.
+pub const DEFAULT_TTL: Duration = Duration::from_secs(3600);
+
+/// Kind of a link with host main domain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum LinkKind {
+    /// Link to unrestrict with unrestrict_link.
+    FILE(String),
+    /// Link to expand with unrestrict_folder.
+    FOLDER(String),
+    UNSUPPORTED,
+}
+
+/// Compiled patterns of supported hosts.
+#[derive(Debug, Clone)]
+pub struct HostPatterns {
+    files: RegexSet,
+    folders: RegexSet,
+    domains: Vec<String>,
+}
+
+impl HostPatterns {
+
+    /// Compile patterns of hosts/regex, hosts/regexFolder and domains of hosts/domains.
+    /// Patterns which are not valid for Rust regex are ignored, INVALID_PATTERN when remaining ones can not be compiled together.
+    pub fn compile(regex: &[String], regex_folder: &[String], domains: &[String]) -> Result<HostPatterns, RDError> {
+        Ok(HostPatterns {
+            files: Self::compile_set(regex)?,
+            folders: Self::compile_set(regex_folder)?,
+            domains: domains.iter().map(|d| d.to_ascii_lowercase()).collect(),
+        })
+    }
+
+    fn compile_set(patterns: &[String]) -> Result<RegexSet, RDError> {
+        let patterns = patterns.iter()
+            .map(|pattern| Self::translate(pattern))
+            .filter(|pattern| Regex::new(pattern).is_ok())
+            .collect::<Vec<_>>();
+        RegexSet::new(patterns).map_err(RDError::INVALID_PATTERN)
+    }
+
+    /// Translate a JS regex literal like /pattern/i to a Rust pattern.
+    pub(crate) fn translate(pattern: &str) -> String {
+        let pattern = pattern.trim();
+        let Some(body) = pattern.strip_prefix('/') else {
+            return pattern.to_string();
+        };
+        let Some(end) = body.rfind('/') else {
+            return body.to_string();
+        };
+
+        let flags = body[end + 1..].chars().filter(|c| matches!(c, 'i' | 'm' | 's')).collect::<String>();
+        if flags.is_empty() {
+            body[..end].to_string()
+        }
+        else {
+            format!("(?{}){}", flags, &body[..end])
+        }
+    }
+
+    /// Classify a link without calling the api.
+    pub fn classify(&self, link: &str) -> LinkKind {
+        let link = link.trim();
+        let folder = self.folders.is_match(link);
+        if !folder && !self.files.is_match(link) {
+            return LinkKind::UNSUPPORTED;
+        }
+
+        match self.host(link) {
+            Some(host) if folder => LinkKind::FOLDER(host),
+            Some(host) => LinkKind::FILE(host),
+            None => LinkKind::UNSUPPORTED,
+        }
+    }
+
+    /// Main domain of link host, None when host is not listed in domains.
+    /// Patterns are not anchored, so they may match a supported link inside another url.
+    fn host(&self, link: &str) -> Option<String> {
+        let url = match Url::parse(link) {
+            Ok(url) => url,
+            Err(_) => Url::parse(format!("https://{}", link).as_str()).ok()?,
+        };
+        let host = url.host_str()?.to_ascii_lowercase();
+        let host = host.strip_prefix("www.").unwrap_or(&host);
+
+        let domain = self.domains.iter()
+            .filter(|domain| host == domain.as_str() || host.ends_with(format!(".{}", domain).as_str()))
+            .max_by_key(|domain| domain.len());
+        domain.map(|d| d.to_string())
+    }
+
+}
+
+/// Classify links locally with patterns of Real-Debrid, refreshed after ttl.
+/// Clones share the same patterns.
+#[derive(Debug, Clone)]
+pub struct HostMatcher {
+    client: RDClient,
+    ttl: Duration,
+    patterns: TtlCache<Arc<HostPatterns>>,
+}
+
+impl HostMatcher {
+
+    pub fn new(client: RDClient) -> HostMatcher {
+        HostMatcher { client, ttl: DEFAULT_TTL, patterns: TtlCache::new() }
+    }
+
+    /// Time before patterns are fetched again.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Classify a link, patterns are fetched on first call and when expired.
+    pub async fn classify(&self, link: &str) -> Result<LinkKind, RDError> {
+        Ok(self.patterns().await?.classify(link))
+    }
+
+    /// Current patterns, fetched if missing or expired.
+    pub async fn patterns(&self) -> Result<Arc<HostPatterns>, RDError> {
+        self.patterns.get(self.ttl, || self.fetch()).await
+    }
+
+    /// Fetch patterns now.
+    pub async fn refresh(&self) -> Result<(), RDError> {
+        self.patterns.set(self.fetch().await?).await;
+        Ok(())
+    }
+
+    async fn fetch(&self) -> Result<Arc<HostPatterns>, RDError> {
+        let (regex, regex_folder, domains) = tokio::try_join!(
+            self.client.get_host_regex(),
+            self.client.get_host_regex_folder(),
+            self.client.get_host_domains(),
+        )?;
+        Ok(Arc::new(HostPatterns::compile(&regex, &regex_folder, &domains)?))
+    }
+
+}
+
+impl RDClient {
+
+    /// Create matcher classifying links with supported hosts.
+    pub fn host_matcher(&self) -> HostMatcher {
+        HostMatcher::new(self.clone())
+    }
+
+}
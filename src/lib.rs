@@ -1,54 +1,300 @@
+#![allow(async_fn_in_trait)]
+
 pub mod data_struct;
+pub mod builder;
+pub mod auth_code;
+pub mod credential;
+pub mod device_flow;
+pub mod rate_limit;
+pub mod retry;
+pub mod bandwidth;
+pub mod pagination;
+pub mod host_matcher;
+pub mod batch;
+pub mod downloader;
+pub mod remote_file;
+pub mod torrent_watcher;
+pub mod file_selector;
+pub mod torrent_meta;
+pub mod magnet;
+pub mod dedup;
+pub mod submission_queue;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+mod session;
+mod state_file;
+mod ttl_cache;
 
 use std::collections::HashMap;
-use std::thread;
+use std::sync::Arc;
 use std::time::{Duration, SystemTime};
-use reqwest::{Client, Response, StatusCode};
+use reqwest::{Client, Request, RequestBuilder, Response, StatusCode, Url};
+use reqwest::header::{HeaderValue, AUTHORIZATION, RETRY_AFTER};
+use serde::de::DeserializeOwned;
 use tokio::fs;
 use tokio::fs::File;
 use tokio::io::AsyncReadExt;
+use crate::builder::{RDClientBuilder, BASE_URL, CLIENT_ID, OAUTH_URL};
+use crate::credential::{CredentialStore, Credentials};
 use crate::data_struct::download::{Downloads, Download, ParamsDownload};
 use crate::data_struct::{RDError, RDOk};
-use crate::data_struct::auth::{AuthCredential, AuthDevice, AuthRefresh, AuthToken};
+use crate::data_struct::container::{ContainerType, ParamsContainer};
+use crate::data_struct::error::{ApiError, ApiErrorBody};
+use crate::data_struct::auth::{AuthRefresh, AuthToken};
 use crate::data_struct::host::{Host, Hosts};
+use crate::data_struct::settings::{ParamsSetting, Settings};
 use crate::data_struct::streaming::{MediaInfo, ParamsStreaming, StreamingTranscode};
 use crate::data_struct::torrent::{ParamsTorrent, ParamsTorrentFile, ParamsTorrentHost, Torrent, TorrentAdd, TorrentCount, TorrentHost, Torrents};
 use crate::data_struct::traffic::{Traffic, TrafficPeriod, Traffics, TrafficsPeriod};
 use crate::data_struct::unrestrict::{Unrestrict, UnrestrictCheck};
 use crate::data_struct::user::User;
-
-const BASE_URL: &'static str = "https://api.real-debrid.com/rest/1.0/";
-const CLIENT_ID: &'static str = "X245A4XAIBGVM";
+use crate::bandwidth::BandwidthLimiter;
+use crate::rate_limit::RateLimits;
+use crate::retry::RetryPolicy;
+use crate::session::{Session, SessionState, REFRESH_MARGIN};
+use crate::torrent_meta::TorrentMeta;
 
 /// Real-Debrid API Documentation : https://api.real-debrid.com/
-#[derive(Default, Debug, Clone)]
+#[derive(Debug, Clone)]
 pub struct RDClient {
     client: Client,
-    token: String,
-    refresh_authorization: Option<AuthRefresh>,
+    session: Arc<Session>,
+    auto_refresh: bool,
+    store: Option<Arc<dyn CredentialStore>>,
+    rate_limits: RateLimits,
+    retry: Option<RetryPolicy>,
+    /// Limit of downloads done through this client.
+    bandwidth: Option<BandwidthLimiter>,
+    base_url: String,
+    oauth_url: String,
+    client_id: String,
+}
+
+impl Default for RDClient {
+    fn default() -> Self {
+        RDClient::new(String::new())
+    }
 }
 
+impl RDClient {
+
+    /// Create builder to configure RDClient.
+    pub fn builder() -> RDClientBuilder {
+        RDClientBuilder::new()
+    }
+
+    /// Create RDClient from credentials saved in store.
+    /// Token is refreshed automatically and written back in store.
+    pub fn from_store(store: impl CredentialStore + 'static) -> Result<RDClient, RDError> {
+        let client = RDClientBuilder::new().credential_store(store).auto_refresh(true).build()?;
+        if client.session.expires_in().is_none() {
+            return Err(RDError::NO_CREDENTIALS);
+        }
+        Ok(client)
+    }
+
+    /// Save current oauth2 session in store.
+    pub fn save_to(&self, store: &dyn CredentialStore) -> Result<(), RDError> {
+        let credentials = self.credentials().ok_or(RDError::NOT_OAUTH2)?;
+        store.save(&credentials)
+    }
+
+    /// Current oauth2 session, None when client use an api key.
+    pub fn credentials(&self) -> Option<Credentials> {
+        Credentials::from_state(&self.session.state())
+    }
+
+    /// Send request with current token.
+    /// With auto refresh, token is refreshed before expiry or after a 401 and request is sent again once.
+    async fn send(&self, request: RequestBuilder) -> Result<Response, RDError> {
+        let request = request.build()?;
+
+        if self.auto_refresh && self.session.expires_in().is_some_and(|left| left <= REFRESH_MARGIN) {
+            self.refresh_session(Some(self.session.token().as_str())).await?;
+        }
+
+        let retry = request.try_clone();
+        let token = self.session.token();
+        let response = self.execute(request, Some(token.as_str())).await?;
+
+        if self.auto_refresh && response.status() == StatusCode::UNAUTHORIZED && self.session.expires_in().is_some() {
+            if let Some(retry) = retry {
+                self.refresh_session(Some(token.as_str())).await?;
+                return self.execute(retry, Some(self.session.token().as_str())).await;
+            }
+        }
+
+        Ok(response)
+    }
+
+    /// Send request, again while it fails with an error accepted by retry policy.
+    async fn execute(&self, mut request: Request, token: Option<&str>) -> Result<Response, RDError> {
+        let mut attempt = 1;
+        loop {
+            let Some(policy) = self.retry.as_ref().filter(|policy| policy.allows(request.method(), attempt)) else {
+                return self.execute_once(request, token).await;
+            };
+            let Some(next) = request.try_clone() else {
+                return self.execute_once(request, token).await;
+            };
+
+            let mut delay = policy.delay(attempt);
+            let error = match self.execute_once(request, token).await {
+                Ok(response) if response.status() == StatusCode::TOO_MANY_REQUESTS || response.status().is_server_error() => {
+                    if let Some(retry_after) = Self::retry_after(&response) {
+                        delay = delay.max(retry_after);
+                    }
+                    RDError::from_response(response).await
+                },
+                Ok(response) => return Ok(response),
+                Err(e) => e,
+            };
+            if !policy.should_retry(&error) {
+                return Err(error);
+            }
+
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+            request = next;
+        }
+    }
+
+    /// Send request once rate limit allow it.
+    async fn execute_once(&self, mut request: Request, token: Option<&str>) -> Result<Response, RDError> {
+        if let Some(token) = token {
+            let auth = HeaderValue::from_str(Self::create_auth(token.to_string()).as_str()).map_err(|_| RDError::BAD_TOKEN)?;
+            request.headers_mut().insert(AUTHORIZATION, auth);
+        }
+        self.rate_limits.acquire(self.endpoint(request.url())).await;
+        Ok(self.client.execute(request).await?)
+    }
+
+    /// Delay asked by server before sending request again.
+    fn retry_after(response: &Response) -> Option<Duration> {
+        let seconds = response.headers().get(RETRY_AFTER)?.to_str().ok()?.parse::<u64>().ok()?;
+        Some(Duration::from_secs(seconds))
+    }
+
+    /// Path of url after base url, used to find endpoint rate limit.
+    fn endpoint<'a>(&self, url: &'a Url) -> &'a str {
+        url.as_str().strip_prefix(self.base_url.as_str()).unwrap_or(url.path())
+    }
+
+    /// Refresh access token shared by all clones.
+    /// When used_token is given and token changed since, another clone already refreshed it.
+    async fn refresh_session(&self, used_token: Option<&str>) -> Result<RDOk, RDError> {
+        let _guard = self.session.refresh_lock.lock().await;
+
+        let mut state = self.session.state();
+        if used_token.is_some_and(|token| token != state.token) {
+            return Ok(RDOk::AUTH_REFRESH);
+        }
+        let Some(auth_refresh) = state.refresh_authorization.clone() else {
+            return Err(RDError::NOT_REFRESH_TOKEN);
+        };
+
+        let mut params = HashMap::new();
+        params.insert("client_id", auth_refresh.client_id.to_string());
+        params.insert("client_secret", auth_refresh.client_secret.to_string());
+        params.insert("code", auth_refresh.refresh_token);
+        params.insert("grant_type", "http://oauth.net/grant_type/device/1.0".to_string());
+
+        let response = self.send_public(self.client.post(format!("{}token", self.oauth_url)).form(&params)).await?;
+        let status = response.status();
+        if status.is_success() {
+            let token = Self::parse::<AuthToken>(response).await?;
+            self.start_session(auth_refresh.client_id, auth_refresh.client_secret, token).await?;
+            return Ok(RDOk::AUTH_REFRESH);
+        }
+
+        // Only a refusal of refresh token end session, other errors may succeed on next refresh.
+        let bytes = response.bytes().await?;
+        match serde_json::from_slice::<ApiErrorBody>(&bytes) {
+            Ok(_) if matches!(status, StatusCode::BAD_REQUEST | StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN) => {
+                state.refresh_authorization = None;
+                self.session.set(state);
+                Err(RDError::REFRESH_FAILED)
+            },
+            Ok(body) => Err(RDError::from_api(ApiError::from(body))),
+            Err(_) => Err(RDError::from_status(status)),
+        }
+    }
+
+    /// Ask a token to oauth2 token endpoint.
+    pub(crate) async fn request_token(&self, params: &HashMap<&str, String>) -> Result<AuthToken, RDError> {
+        let response = self.send_public(self.client.post(format!("{}token", self.oauth_url)).form(params)).await?;
+
+        if response.status().is_success() {
+            Self::parse::<AuthToken>(response).await
+        }
+        else {
+            Err(RDError::from_response(response).await)
+        }
+    }
+
+    /// Use token received from oauth2 and save it in store.
+    /// Store may block on files, so it is called on blocking threads.
+    pub(crate) async fn start_session(&self, client_id: String, client_secret: String, token: AuthToken) -> Result<(), RDError> {
+        let auth_refresh = AuthRefresh { client_id, client_secret, refresh_token: token.refresh_token, auth_time: SystemTime::now(), expires_in: token.expires_in };
+        self.session.set(SessionState { token: token.access_token, refresh_authorization: Some(auth_refresh) });
+        if let Some(store) = self.store.clone() {
+            let client = self.clone();
+            tokio::task::spawn_blocking(move || client.save_to(store.as_ref())).await
+                .map_err(|e| RDError::IO(std::io::Error::other(e)))??;
+        }
+        Ok(())
+    }
+
+    /// Send request without token.
+    async fn send_public(&self, request: RequestBuilder) -> Result<Response, RDError> {
+        self.execute(request.build()?, None).await
+    }
+
+    /// Read json body of response.
+    async fn parse<T: DeserializeOwned>(response: Response) -> Result<T, RDError> {
+        let bytes = response.bytes().await?;
+        Ok(serde_json::from_slice::<T>(&bytes)?)
+    }
+
+    /// Read total count of a list.
+    fn total_count(response: &Response) -> Option<u64> {
+        response.headers().get("x-total-count")?.to_str().ok()?.parse::<u64>().ok()
+    }
+
+}
 
 pub trait RDTraitAsync {
     async fn auth() -> Result<RDClient, RDError> ;
 
-    async fn refresh_token(&mut self) -> Result<RDOk, RDError> ;
+    async fn refresh_token(&self) -> Result<RDOk, RDError> ;
 
-    async fn get_time() -> String ;
+    async fn get_time(&self) -> Result<String, RDError> ;
 
-    async fn get_time_iso() -> String ;
+    async fn get_time_iso(&self) -> Result<String, RDError> ;
 
-    async fn disable_access_token(&self) -> Result<(), ()> ;
+    async fn disable_access_token(&self) -> Result<(), RDError> ;
 
     async fn get_user(&self) -> Result<User, RDError> ;
 
+    async fn get_settings(&self) -> Result<Settings, RDError> ;
+
+    async fn update_setting(&self, setting: ParamsSetting) -> Result<(), RDError> ;
+
+    async fn convert_points(&self) -> Result<(), RDError> ;
+
+    async fn change_password(&self) -> Result<(), RDError> ;
+
+    async fn upload_avatar(&self, path: String) -> Result<(), RDError> ;
+
+    async fn delete_avatar(&self) -> Result<RDOk, RDError> ;
+
     async fn check_unrestrict(&self, link: String, hoster_password: Option<String>) -> Result<UnrestrictCheck, RDError> ;
 
     async fn unrestrict_link(&self, link: String, hoster_password: Option<String>, remote: Option<bool>) -> Result<Unrestrict, RDError> ;
 
     async fn unrestrict_folder(&self, link: String) -> Result<Vec<String>, RDError> ;
 
-    async fn unrestrict_decrypt_special_folder(&self) -> Result<Vec<String>, RDError> ;
+    async fn unrestrict_decrypt_special_folder(&self, container: ParamsContainer) -> Result<Vec<String>, RDError> ;
 
     async fn unrestrict_decrypt_folder(&self, link: String) -> Result<Vec<String>, RDError> ;
 
@@ -74,58 +320,56 @@ pub trait RDTraitAsync {
 
     async fn add_torrent_file(&self, path: String, host: Option<ParamsTorrentHost>) -> Result<TorrentAdd, RDError> ;
 
-    async fn add_torrent_magnet(&self, magnet: String, host: Option<ParamsTorrentHost>) -> Result<TorrentAdd, RDError> ;
+    async fn add_torrent_magnet(&self, magnet: impl Into<String>, host: Option<ParamsTorrentHost>) -> Result<TorrentAdd, RDError> ;
 
     async fn select_torrent_file(&self, torrent: ParamsTorrent, files: ParamsTorrentFile) -> Result<(),RDError> ;
 
     async fn remove_torrent(&self, torrent: ParamsTorrent) -> Result<RDOk, RDError> ;
 
-    async fn get_host() -> Hosts ;
+    async fn get_host(&self) -> Result<Hosts, RDError> ;
 
-    async fn get_host_regex() -> Vec<String> ;
+    async fn get_host_regex(&self) -> Result<Vec<String>, RDError> ;
 
-    async fn get_host_regex_folder() -> Vec<String> ;
+    async fn get_host_regex_folder(&self) -> Result<Vec<String>, RDError> ;
 
-    async fn get_host_domains() -> Vec<String> ;
+    async fn get_host_domains(&self) -> Result<Vec<String>, RDError> ;
 
     async fn get_host_with_status(&self) -> Result<Hosts, RDError> ;
 
 }
 
 pub trait RDTrait {
-    fn new(api_key: String) -> RDClient;
+    fn new(api_key: String) -> Self;
     fn auth_valid(&self) -> Result<bool, RDError>;
     fn change_api_key(&mut self, api_key: String);
-    fn create_link(other_part: &str, params: Option<&str>) -> String;
+    fn create_link(&self, other_part: &str, params: Option<&str>) -> String;
     fn create_auth(api_key: String) -> String;
 }
 
 impl RDTrait for RDClient {
     
     /// Create new RDClient with api key.
-    fn new(api_key: String) -> RDClient {
-        RDClient { client: Client::new(), token: api_key, refresh_authorization: None }
+    fn new(api_key: String) -> Self {
+        RDClient { client: Client::new(), session: Arc::new(Session::new(api_key, None)), auto_refresh: false, store: None, rate_limits: RateLimits::default(), retry: None, bandwidth: None, base_url: BASE_URL.to_string(), oauth_url: OAUTH_URL.to_string(), client_id: CLIENT_ID.to_string() }
     }
 
-    /// Check if oauth2 is valid or if is necessary to refresh.
+    /// Check if oauth2 token is still valid, false when is necessary to refresh.
     fn auth_valid(&self) -> Result<bool, RDError> {
-        if self.refresh_authorization.is_none() {
-            Err(RDError::NOT_OAUTH2)
-        }
-        else {
-            let auth_refresh = self.refresh_authorization.clone().unwrap();
-            Ok(auth_refresh.auth_time.elapsed().unwrap().gt(&Duration::from_secs(auth_refresh.expires_in)))
+        match self.session.expires_in() {
+            None => Err(RDError::NOT_OAUTH2),
+            Some(left) => Ok(!left.is_zero()),
         }
     }
 
     /// Change current api key with new api key.
+    /// Clones of this client use the new api key too.
     fn change_api_key(&mut self, api_key: String) {
-        self.token = api_key;
+        self.session.set_token(api_key);
     }
 
-    /// Create link with BASE_URL and other part add options.
-    fn create_link(other_part: &str, params: Option<&str>) -> String {
-        format!("{}{}?{}", BASE_URL , other_part, params.unwrap_or(""))
+    /// Create link with base url and other part add options.
+    fn create_link(&self, other_part: &str, params: Option<&str>) -> String {
+        format!("{}{}?{}", self.base_url , other_part, params.unwrap_or(""))
     }
 
     /// Create header format for auth with api key.
@@ -138,174 +382,185 @@ impl RDTrait for RDClient {
 impl RDTraitAsync for RDClient {
 
     /// Create new RDClient with oauth2.
+    /// Legacy shim printing verification link on stdout.
+    #[allow(deprecated)]
     async fn auth() -> Result<RDClient, RDError> {
+        RDClientBuilder::new().auth().await
+    }
 
-        let client = Client::new();
-
-        let response1 = client.get(format!("https://api.real-debrid.com/oauth/v2/device/code?client_id={}&new_credentials=oui", CLIENT_ID)).send().await.unwrap();
-        if response1.status() != StatusCode::OK {
-            return Err(RDError::AUTH_FAILED);
-        }
+    /// Refresh RDClient when use oauth2.
+    /// Token is shared with all clones of this client.
+    async fn refresh_token(&self) -> Result<RDOk, RDError> {
+        self.refresh_session(None).await
+    }
 
-        let result1 = response1.json::<AuthDevice>().await.unwrap();
+    /// Get server time.
+    async fn get_time(&self) -> Result<String, RDError> {
+        let response = self.send_public(self.client.get(self.create_link("time", None))).await?;
 
-        println!("Verifie device on link {} with code {}", result1.verification_url, result1.user_code);
+        if response.status().is_success() {
+            Ok(response.text().await?)
+        }
+        else {
+            Err(RDError::from_response(response).await)
+        }
+    }
 
-        let mut pass = 0;
-        let mut response2: Response;
-        loop {
-            if pass * result1.interval > result1.expires_in {
-                return Err(RDError::AUTH_FAILED);
-            }
-            pass += 1;
+    /// Get server time in ISO.
+    async fn get_time_iso(&self) -> Result<String, RDError> {
+        let response = self.send_public(self.client.get(self.create_link("time/iso", None))).await?;
 
-            response2 = client.get(format!("https://api.real-debrid.com/oauth/v2/device/credentials?client_id={}&code={}", CLIENT_ID, result1.device_code.clone())).send().await.unwrap();
-            if response2.status() != StatusCode::OK {
-                thread::sleep(Duration::from_secs(result1.interval));
-            }
-            else {
-                break;
-            }
+        if response.status().is_success() {
+            Ok(response.text().await?)
         }
+        else {
+            Err(RDError::from_response(response).await)
+        }
+    }
 
-        let result2 = response2.json::<AuthCredential>().await.unwrap();
-
-        let mut params = HashMap::new();
-        params.insert("client_id", result2.client_id.to_string());
-        params.insert("client_secret", result2.client_secret.to_string());
-        params.insert("code", result1.device_code);
-        params.insert("grant_type", "http://oauth.net/grant_type/device/1.0".to_string());
+    /// Disable current access token
+    async fn disable_access_token(&self) -> Result<(), RDError> {
+        let response = self.send(self.client.get(self.create_link("disable_access_token", None))).await?;
 
-        let response3 = client.post("https://api.real-debrid.com/oauth/v2/token").form(&params).send().await.unwrap();
-        if response3.status() != StatusCode::OK {
-            return Err(RDError::AUTH_FAILED);
+        if response.status().is_success() {
+            Ok(())
         }
+        else {
+            Err(RDError::from_response(response).await)
+        }
+    }
 
-        let result3 = response3.json::<AuthToken>().await.unwrap();
-
-        let auth_refresh = AuthRefresh { client_id: result2.client_id , client_secret: result2.client_secret, refresh_token: result3.refresh_token, auth_time: SystemTime::now(), expires_in: result3.expires_in};
+    /// Get current user info.
+    async fn get_user(&self) -> Result<User, RDError> {
+        let response = self.send(self.client.get(self.create_link("user", None))).await?;
 
-        Ok(RDClient { client, token: result3.access_token, refresh_authorization: Some(auth_refresh) })
+        if response.status().is_success() {
+            Self::parse::<User>(response).await
+        }
+        else {
+            Err(RDError::from_response(response).await)
+        }
     }
 
-    /// Refresh RDClient when use oauth2.
-    async fn refresh_token(&mut self) -> Result<RDOk, RDError> {
-        if self.refresh_authorization.is_none() {
-            Err(RDError::NOT_REFRESH_TOKEN)
-        }
-        else {
-            let mut auth_refresh = self.refresh_authorization.clone().unwrap();
-            let mut params = HashMap::new();
-            params.insert("client_id", auth_refresh.client_id.to_string());
-            params.insert("client_secret", auth_refresh.client_secret.to_string());
-            params.insert("code", auth_refresh.refresh_token);
-            params.insert("grant_type", "http://oauth.net/grant_type/device/1.0".to_string());
-
-            let response = self.client.post("https://api.real-debrid.com/oauth/v2/token").form(&params).send().await.unwrap();
-            if response.status() != StatusCode::OK {
-                self.refresh_authorization = None;
-                return Err(RDError::REFRESH_FAILED);
-            }
+    /// Get current user settings.
+    async fn get_settings(&self) -> Result<Settings, RDError> {
+        let response = self.send(self.client.get(self.create_link("settings", None))).await?;
 
-            let result = response.json::<AuthToken>().await.unwrap();
-
-            auth_refresh.refresh_token = result.refresh_token;
-            auth_refresh.auth_time = SystemTime::now();
-            auth_refresh.expires_in = result.expires_in;
+        if response.status().is_success() {
+            Self::parse::<Settings>(response).await
+        }
+        else {
+            Err(RDError::from_response(response).await)
+        }
+    }
 
-            self.refresh_authorization = Some(auth_refresh);
+    /// Update a user setting.
+    async fn update_setting(&self, setting: ParamsSetting) -> Result<(), RDError> {
+        let mut params = HashMap::new();
+        params.insert("setting_name", setting.name().to_string());
+        params.insert("setting_value", setting.value());
 
-            Ok(RDOk::AUTH_REFRESH)
+        let response = self.send(self.client.post(self.create_link("settings/update", None)).form(&params)).await?;
 
+        if response.status().is_success() {
+            Ok(())
+        }
+        else {
+            Err(RDError::from_response(response).await)
         }
     }
 
-    /// Get server time.
-    async fn get_time() -> String {
-        reqwest::get(Self::create_link("time", None)).await.unwrap().text().await.unwrap()
-    }
+    /// Convert fidelity points.
+    async fn convert_points(&self) -> Result<(), RDError> {
+        let response = self.send(self.client.post(self.create_link("settings/convertPoints", None))).await?;
 
-    /// Get server time in ISO.
-    async fn get_time_iso() -> String {
-        reqwest::get(Self::create_link("time/iso", None)).await.unwrap().text().await.unwrap()
+        if response.status().is_success() {
+            Ok(())
+        }
+        else {
+            Err(RDError::from_response(response).await)
+        }
     }
 
-    /// Disable current access token
-    async fn disable_access_token(&self) -> Result<(), ()> {
+    /// Send the verification email to change the password.
+    async fn change_password(&self) -> Result<(), RDError> {
+        let response = self.send(self.client.post(self.create_link("settings/changePassword", None))).await?;
 
-        if self.client.get(Self::create_link("disable_access_token", None)).bearer_auth(self.token.clone()).send().await.unwrap().status() == StatusCode::OK {
+        if response.status().is_success() {
             Ok(())
         }
         else {
-            Err(())
+            Err(RDError::from_response(response).await)
         }
-
     }
 
-    /// Get current user info.
-    async fn get_user(&self) -> Result<User, RDError> {
+    /// Upload a new user avatar image.
+    async fn upload_avatar(&self, path: String) -> Result<(), RDError> {
+        if !fs::try_exists(path.clone()).await? {
+            return Err(RDError::PATH_NOT_RIGHT);
+        }
 
-        let response = self.client.get(Self::create_link("user", None)).bearer_auth(self.token.clone()).send().await.unwrap();
+        let buffer = fs::read(path).await?;
 
-        if response.status() == StatusCode::FORBIDDEN {
-            Err(RDError::PERMISSION_DENIED)
-        }
-        else if response.status() == StatusCode::UNAUTHORIZED {
-            Err(RDError::BAD_TOKEN)
+        let response = self.send(self.client.put(self.create_link("settings/avatarFile", None)).body(buffer)).await?;
+
+        if response.status().is_success() {
+            Ok(())
         }
         else {
-            Ok(response.json::<User>().await.unwrap())
+            Err(RDError::from_response(response).await)
         }
+    }
+
+    /// Reset user avatar image to default.
+    async fn delete_avatar(&self) -> Result<RDOk, RDError> {
+        let response = self.send(self.client.delete(self.create_link("settings/avatarDelete", None))).await?;
 
+        if response.status() == StatusCode::NO_CONTENT {
+            Ok(RDOk::REMOVED_SUCCESS)
+        }
+        else {
+            Err(RDError::from_response(response).await)
+        }
     }
 
     /// Check a link.
     async fn check_unrestrict(&self, link: String, hoster_password: Option<String>) -> Result<UnrestrictCheck, RDError> {
-
         let mut params = HashMap::new();
         params.insert("link", link);
-        if hoster_password.is_some() {
-            params.insert("password", hoster_password.unwrap());
+        if let Some(hoster_password) = hoster_password {
+            params.insert("password", hoster_password);
         }
 
-        let response = self.client.post(Self::create_link("unrestrict/check", None)).bearer_auth(self.token.clone()).form(&params).send().await.unwrap();
+        let response = self.send(self.client.post(self.create_link("unrestrict/check", None)).form(&params)).await?;
 
-        if response.status() == StatusCode::SERVICE_UNAVAILABLE {
-            Err(RDError::FILE_UNAVAILABLE)
-        }
-        else if response.status() == StatusCode::BAD_REQUEST {
-            Err(RDError::BAD_REQUEST)
+        if response.status().is_success() {
+            Self::parse::<UnrestrictCheck>(response).await
         }
         else {
-            Ok(response.json::<UnrestrictCheck>().await.unwrap())
+            Err(RDError::from_response(response).await)
         }
-
     }
 
     /// Unrestrict a link.
     async fn unrestrict_link(&self, link: String, hoster_password: Option<String>, remote: Option<bool>) -> Result<Unrestrict, RDError> {
         let mut params = HashMap::new();
-        params.insert("link", link.clone());
-
-        if hoster_password.is_some() {
-            params.insert("password", hoster_password.unwrap());
+        params.insert("link", link);
+        if let Some(hoster_password) = hoster_password {
+            params.insert("password", hoster_password);
         }
-        if remote.is_some() {
-            params.insert("remote", remote.unwrap().to_string());
+        if let Some(remote) = remote {
+            params.insert("remote", (remote as u8).to_string());
         }
 
-        let response = self.client.post(Self::create_link("unrestrict/link", None)).bearer_auth(self.token.clone()).form(&params).send().await.unwrap();
+        let response = self.send(self.client.post(self.create_link("unrestrict/link", None)).form(&params)).await?;
 
-        if response.status() == StatusCode::UNAUTHORIZED {
-            Err(RDError::BAD_TOKEN)
-        }
-        else if response.status() == StatusCode::FORBIDDEN {
-            Err(RDError::PERMISSION_DENIED)
+        if response.status().is_success() {
+            Self::parse::<Unrestrict>(response).await
         }
         else {
-            Ok(response.json::<Unrestrict>().await.unwrap())
+            Err(RDError::from_response(response).await)
         }
-
     }
 
     /// Unrestrict a folder link.
@@ -313,41 +568,47 @@ impl RDTraitAsync for RDClient {
         let mut params = HashMap::new();
         params.insert("link", link);
 
-        let response = self.client.post(Self::create_link("unrestrict/folder", None)).bearer_auth(self.token.clone()).form(&params).send().await.unwrap();
+        let response = self.send(self.client.post(self.create_link("unrestrict/folder", None)).form(&params)).await?;
 
-        if response.status() == StatusCode::UNAUTHORIZED {
-            Err(RDError::BAD_TOKEN)
-        }
-        else if response.status() == StatusCode::FORBIDDEN {
-            Err(RDError::PERMISSION_DENIED)
+        if response.status().is_success() {
+            Self::parse::<Vec<String>>(response).await
         }
         else {
-            Ok(response.json::<Vec<String>>().await.unwrap())
+            Err(RDError::from_response(response).await)
         }
-
     }
 
-    /// Decrypt container file.
-    async fn unrestrict_decrypt_special_folder(&self) -> Result<Vec<String>, RDError> {
-
-        let response = self.client.put(Self::create_link("unrestrict/containerFile", None)).bearer_auth(self.token.clone()).send().await.unwrap();
+    /// Decrypt container file (DLC, RSDF, CCF or CCF3).
+    /// Unsupported files are rejected before upload.
+    async fn unrestrict_decrypt_special_folder(&self, container: ParamsContainer) -> Result<Vec<String>, RDError> {
+        let (name, buffer) = match container {
+            ParamsContainer::FROM_PATH(path) => {
+                if !fs::try_exists(path.clone()).await? {
+                    return Err(RDError::PATH_NOT_RIGHT);
+                }
+                let buffer = fs::read(&path).await?;
+                (Some(path), buffer)
+            },
+            ParamsContainer::FROM_BYTES(d) => (None, d),
+            ParamsContainer::FROM_READER(mut d) => {
+                let mut buffer = Vec::new();
+                d.read_to_end(&mut buffer).await?;
+                (None, buffer)
+            },
+        };
 
-        if response.status() == StatusCode::UNAUTHORIZED {
-            Err(RDError::BAD_TOKEN)
-        }
-        else if response.status() == StatusCode::BAD_REQUEST {
-            Err(RDError::BAD_REQUEST)
+        if ContainerType::detect(name.as_deref(), &buffer).is_none() {
+            return Err(RDError::UNSUPPORTED_CONTAINER);
         }
-        else if response.status() == StatusCode::FORBIDDEN {
-            Err(RDError::NOT_PREMIUM)
-        }
-        else if response.status() == StatusCode::SERVICE_UNAVAILABLE {
-            Err(RDError::SERVICE_UNAVAILABLE)
+
+        let response = self.send(self.client.put(self.create_link("unrestrict/containerFile", None)).body(buffer)).await?;
+
+        if response.status().is_success() {
+            Self::parse::<Vec<String>>(response).await
         }
         else {
-            Ok(response.json::<Vec<String>>().await.unwrap())
+            Err(RDError::from_response(response).await)
         }
-
     }
 
     /// Decrypt container file from link.
@@ -355,363 +616,265 @@ impl RDTraitAsync for RDClient {
         let mut params = HashMap::new();
         params.insert("link", link);
 
-        let response = self.client.post(Self::create_link("unrestrict/containerLink", None)).bearer_auth(self.token.clone()).form(&params).send().await.unwrap();
+        let response = self.send(self.client.post(self.create_link("unrestrict/containerLink", None)).form(&params)).await?;
 
-        if response.status() == StatusCode::UNAUTHORIZED {
-            Err(RDError::BAD_TOKEN)
-        }
-        else if response.status() == StatusCode::FORBIDDEN {
-            Err(RDError::PERMISSION_DENIED)
+        if response.status().is_success() {
+            Self::parse::<Vec<String>>(response).await
         }
         else {
-            Ok(response.json::<Vec<String>>().await.unwrap())
+            Err(RDError::from_response(response).await)
         }
-
     }
 
     /// Traffic informations for limited hosters.
     async fn get_traffic(&self) -> Result<Traffics, RDError> {
+        let response = self.send(self.client.get(self.create_link("traffic", None))).await?;
 
-        let response = self.client.get(Self::create_link("traffic", None)).bearer_auth(self.token.clone()).send().await.unwrap();
-
-        if response.status() == StatusCode::FORBIDDEN {
-            Err(RDError::PERMISSION_DENIED)
-        }
-        else if response.status() == StatusCode::UNAUTHORIZED {
-            Err(RDError::BAD_TOKEN)
+        if response.status().is_success() {
+            Ok(Traffics { result: Self::parse::<HashMap<String, Traffic>>(response).await? })
         }
         else {
-            let mut traffic: Traffics = Traffics::default();
-            traffic.result = response.json::<HashMap<String, Traffic>>().await.unwrap();
-            Ok(traffic)
+            Err(RDError::from_response(response).await)
         }
-
     }
 
     /// Traffic details on used hosters.
     /// start : Date(YYYY-MM-DD) and end : Date(YYYY-MM-DD).
     async fn get_traffic_details(&self, start: Option<String> , end: Option<String> ) -> Result<TrafficsPeriod, RDError> {
         let mut params: String = String::new();
-        if start.is_some() {
-            params.push_str(format!("start={}&", start.unwrap()).as_str());
+        if let Some(start) = start {
+            params.push_str(format!("start={}&", start).as_str());
         }
-        if end.is_some() {
-            params.push_str(format!("end={}&", end.unwrap()).as_str());
+        if let Some(end) = end {
+            params.push_str(format!("end={}&", end).as_str());
         }
 
-        let response = self.client.get(Self::create_link("traffic/details", Some(params.as_str()))).bearer_auth(self.token.clone()).send().await.unwrap();
+        let response = self.send(self.client.get(self.create_link("traffic/details", Some(params.as_str())))).await?;
 
-        if response.status() == StatusCode::FORBIDDEN {
-            Err(RDError::PERMISSION_DENIED)
-        }
-        else if response.status() == StatusCode::UNAUTHORIZED {
-            Err(RDError::BAD_TOKEN)
+        if response.status().is_success() {
+            Ok(TrafficsPeriod { result: Self::parse::<HashMap<String, TrafficPeriod>>(response).await? })
         }
         else {
-            let mut traffic = TrafficsPeriod::default();
-            traffic.result = response.json::<HashMap<String, TrafficPeriod>>().await.unwrap();
-            Ok(traffic)
+            Err(RDError::from_response(response).await)
         }
-
     }
 
     /// Get transcoding links for given file.
-    #[allow(unused_assignments)]
     async fn get_streaming_transcode(&self, streaming: ParamsStreaming) -> Result<StreamingTranscode, RDError> {
-        let mut id_streaming = String::new();
-        match streaming {
-            ParamsStreaming::FROM_DOWNLOAD(d) => id_streaming = d.id().to_string(),
-            ParamsStreaming::FROM_UNRESTRICT(d) =>  id_streaming = d.id().to_string(),
-            ParamsStreaming::FROM_ID(d) => id_streaming = d,
+        let id_streaming = match streaming {
+            ParamsStreaming::FROM_DOWNLOAD(d) => d.id().to_string(),
+            ParamsStreaming::FROM_UNRESTRICT(d) => d.id().to_string(),
+            ParamsStreaming::FROM_ID(d) => d,
         };
 
-        let response = self.client.get(Self::create_link(format!("streaming/transcode/{}", id_streaming).as_str(), None)).bearer_auth(self.token.clone()).send().await.unwrap();
+        let response = self.send(self.client.get(self.create_link(format!("streaming/transcode/{}", id_streaming).as_str(), None))).await?;
 
-        if response.status() == StatusCode::FORBIDDEN {
-            Err(RDError::PERMISSION_DENIED)
-        }
-        else if response.status() == StatusCode::UNAUTHORIZED {
-            Err(RDError::BAD_TOKEN)
+        if response.status().is_success() {
+            Self::parse::<StreamingTranscode>(response).await
         }
         else {
-            Ok(response.json::<StreamingTranscode>().await.unwrap())
+            Err(RDError::from_response(response).await)
         }
     }
 
     /// Get media informations for given file.
-    #[allow(unused_assignments)]
     async fn get_streaming_media_info(&self, streaming: ParamsStreaming) -> Result<MediaInfo, RDError> {
-        let mut id_streaming = String::new();
-        match streaming {
-            ParamsStreaming::FROM_DOWNLOAD(d) => id_streaming = d.id().to_string(),
-            ParamsStreaming::FROM_UNRESTRICT(d) => id_streaming = d.id().to_string(),
-            ParamsStreaming::FROM_ID(d) => id_streaming = d,
+        let id_streaming = match streaming {
+            ParamsStreaming::FROM_DOWNLOAD(d) => d.id().to_string(),
+            ParamsStreaming::FROM_UNRESTRICT(d) => d.id().to_string(),
+            ParamsStreaming::FROM_ID(d) => d,
         };
 
-        let response = self.client.get(Self::create_link(format!("streaming/mediaInfos/{}", id_streaming).as_str(), None)).bearer_auth(self.token.clone()).send().await.unwrap();
+        let response = self.send(self.client.get(self.create_link(format!("streaming/mediaInfos/{}", id_streaming).as_str(), None))).await?;
 
-        if response.status() == StatusCode::FORBIDDEN {
-            Err(RDError::PERMISSION_DENIED)
-        }
-        else if response.status() == StatusCode::UNAUTHORIZED {
-            Err(RDError::BAD_TOKEN)
-        }
-        else if response.status() == StatusCode::SERVICE_UNAVAILABLE {
-            Err(RDError::PROBLEM_FINDING_METADATA)
+        if response.status().is_success() {
+            Self::parse::<MediaInfo>(response).await
         }
         else {
-            Ok(response.json::<MediaInfo>().await.unwrap())
+            Err(RDError::from_response(response).await)
         }
     }
 
     /// Get user downloads list.
     async fn get_downloads(&self, offset: Option<u32>, page: Option<u32>, limit: Option<u32>) -> Result<Downloads, RDError> {
-
         let mut params: String = String::new();
-        if offset.is_some() {
-            params.push_str(format!("offset={}&", offset.unwrap()).as_str());
+        if let Some(offset) = offset {
+            params.push_str(format!("offset={}&", offset).as_str());
         }
-        if page.is_some() {
-            params.push_str(format!("page={}&", page.unwrap()).as_str());
+        if let Some(page) = page {
+            params.push_str(format!("page={}&", page).as_str());
         }
-        if limit.is_some() {
-            params.push_str(format!("limit={}", limit.unwrap()).as_str());
+        if let Some(limit) = limit {
+            params.push_str(format!("limit={}", limit).as_str());
         }
 
+        let response = self.send(self.client.get(self.create_link("downloads", Some(params.as_str())))).await?;
 
-        let response = self.client.get(Self::create_link("downloads", Some(params.as_str()))).bearer_auth(self.token.clone()).send().await.unwrap();
-
-        if response.status() == StatusCode::FORBIDDEN {
-            Err(RDError::PERMISSION_DENIED)
-        }
-        else if response.status() == StatusCode::UNAUTHORIZED {
-            Err(RDError::BAD_TOKEN)
-        }
-        else if response.status() == StatusCode::NO_CONTENT {
+        if response.status() == StatusCode::NO_CONTENT {
             Err(RDError::NO_CONTENT)
         }
+        else if response.status().is_success() {
+            let total_count = Self::total_count(&response);
+            let result = Self::parse::<Vec<Download>>(response).await?;
+            Ok(Downloads { total_count: total_count.unwrap_or(result.len() as u64), result })
+        }
         else {
-            let mut downloads = Downloads::default();
-            downloads.total_count = response.headers().get("x-total-count").unwrap().to_str().unwrap().parse::<u64>().unwrap();
-            downloads.result = response.json::<Vec<Download>>().await.unwrap();
-            Ok(downloads)
+            Err(RDError::from_response(response).await)
         }
-
     }
 
     /// Delete a link from downloads list.
     /// Use Params with id or Download.
-    #[allow(unused_assignments)]
     async fn remove_download(&self, download: ParamsDownload) -> Result<RDOk, RDError> {
-        let mut id_remove = String::new();
-        match download {
-            ParamsDownload::FROM_STRUCT(d) => id_remove = d.id().to_string(),
-            ParamsDownload::FROM_ID(d) => id_remove = d,
-        }
+        let id_remove = match download {
+            ParamsDownload::FROM_STRUCT(d) => d.id().to_string(),
+            ParamsDownload::FROM_ID(d) => d,
+        };
 
-        let response = self.client.delete(Self::create_link(format!("downloads/delete/{}",id_remove).as_str(), None)).bearer_auth(self.token.clone()).send().await.unwrap();
+        let response = self.send(self.client.delete(self.create_link(format!("downloads/delete/{}",id_remove).as_str(), None))).await?;
 
-        if response.status() == StatusCode::FORBIDDEN {
-            Err(RDError::PERMISSION_DENIED)
-        }
-        else if response.status() == StatusCode::UNAUTHORIZED {
-            Err(RDError::BAD_TOKEN)
-        }
-        else if response.status() == StatusCode::NOT_FOUND {
-            Err(RDError::UNKNOWN_RESSOURCE)
-        }
-        else if response.status() == StatusCode::NO_CONTENT  {
+        if response.status() == StatusCode::NO_CONTENT {
             Ok(RDOk::REMOVED_SUCCESS)
         }
         else {
-            println!("{:?}", response.status());
-            Err(RDError::UNDEFINED)
+            Err(RDError::from_response(response).await)
         }
-
     }
 
     /// Get user torrents list.
     async fn get_torrents(&self, offset: Option<u32>, page: Option<u32>, limit: Option<u32>, filter: Option<String>) -> Result<Torrents, RDError> {
-
         let mut params: String = String::new();
-        if offset.is_some() {
-            params.push_str(format!("offset={}&", offset.unwrap()).as_str());
+        if let Some(offset) = offset {
+            params.push_str(format!("offset={}&", offset).as_str());
         }
-        if page.is_some() {
-            params.push_str(format!("page={}&", page.unwrap()).as_str());
+        if let Some(page) = page {
+            params.push_str(format!("page={}&", page).as_str());
         }
-        if limit.is_some() {
-            params.push_str(format!("limit={}", limit.unwrap()).as_str());
+        if let Some(limit) = limit {
+            params.push_str(format!("limit={}&", limit).as_str());
         }
-        if filter.is_some() {
-            params.push_str(format!("filter={}", filter.unwrap()).as_str());
+        if let Some(filter) = filter {
+            params.push_str(format!("filter={}", filter).as_str());
         }
 
-        let response = self.client.get(Self::create_link("torrents", Some(params.as_str()))).bearer_auth(self.token.clone()).send().await.unwrap();
+        let response = self.send(self.client.get(self.create_link("torrents", Some(params.as_str())))).await?;
 
-        if response.status() == StatusCode::FORBIDDEN {
-            Err(RDError::PERMISSION_DENIED)
-        }
-        else if response.status() == StatusCode::UNAUTHORIZED {
-            Err(RDError::BAD_TOKEN)
-        }
-        else if response.status() == StatusCode::NO_CONTENT {
+        if response.status() == StatusCode::NO_CONTENT {
             Err(RDError::NO_CONTENT)
         }
+        else if response.status().is_success() {
+            let total_count = Self::total_count(&response);
+            let result = Self::parse::<Vec<Torrent>>(response).await?;
+            Ok(Torrents { total_count: total_count.unwrap_or(result.len() as u64), result })
+        }
         else {
-            let mut torrents = Torrents::default();
-            torrents.total_count = response.headers().get("x-total-count").unwrap().to_str().unwrap().parse::<u64>().unwrap();
-            torrents.result = response.json::<Vec<Torrent>>().await.unwrap();
-            Ok(torrents)
+            Err(RDError::from_response(response).await)
         }
-
     }
 
     /// Get infos on torrent.
-    #[allow(unused_assignments)]
     async fn get_torrents_info(&self, torrent: ParamsTorrent) -> Result<Torrent, RDError> {
+        let id_torrent = match torrent {
+            ParamsTorrent::FROM_STRUCT(d) => d.id().to_string(),
+            ParamsTorrent::FROM_ADD(d) => d.id().to_string(),
+            ParamsTorrent::FROM_ID(d) => d,
+        };
 
-        let mut id_torrent = String::new();
-
-        match torrent {
-            ParamsTorrent::FROM_STRUCT(d) => id_torrent = d.id().to_string(),
-            ParamsTorrent::FROM_ADD(d) => id_torrent = d.id().to_string(),
-            ParamsTorrent::FROM_ID(d) => id_torrent = d,
-        }
-
-        let response = self.client.get(Self::create_link(format!("torrents/info/{}",id_torrent).as_str(), None)).bearer_auth(self.token.clone()).send().await.unwrap();
+        let response = self.send(self.client.get(self.create_link(format!("torrents/info/{}",id_torrent).as_str(), None))).await?;
 
-        if response.status() == StatusCode::FORBIDDEN {
-            Err(RDError::PERMISSION_DENIED)
-        }
-        else if response.status() == StatusCode::UNAUTHORIZED {
-            Err(RDError::BAD_TOKEN)
-        }
-        else if response.status() == StatusCode::NO_CONTENT {
+        if response.status() == StatusCode::NO_CONTENT {
             Err(RDError::NO_CONTENT)
         }
+        else if response.status().is_success() {
+            Self::parse::<Torrent>(response).await
+        }
         else {
-            Ok(response.json::<Torrent>().await.unwrap())
+            Err(RDError::from_response(response).await)
         }
-
     }
 
     /// Get currently active torrents number.
     async fn get_torrents_active_count(&self) -> Result<TorrentCount, RDError> {
-        let response = self.client.get(Self::create_link("torrents/activeCount", None)).bearer_auth(self.token.clone()).send().await.unwrap();
+        let response = self.send(self.client.get(self.create_link("torrents/activeCount", None))).await?;
 
-        if response.status() == StatusCode::FORBIDDEN {
-            Err(RDError::PERMISSION_DENIED)
-        }
-        else if response.status() == StatusCode::UNAUTHORIZED {
-            Err(RDError::BAD_TOKEN)
+        if response.status().is_success() {
+            Self::parse::<TorrentCount>(response).await
         }
         else {
-            Ok(response.json::<TorrentCount>().await.unwrap())
+            Err(RDError::from_response(response).await)
         }
     }
 
     /// Get available hosts.
     async fn get_torrents_available_hosts(&self) -> Result<Vec<TorrentHost>, RDError> {
-        let response = self.client.get(Self::create_link("torrents/availableHosts", None)).bearer_auth(self.token.clone()).send().await.unwrap();
+        let response = self.send(self.client.get(self.create_link("torrents/availableHosts", None))).await?;
 
-        if response.status() == StatusCode::FORBIDDEN {
-            Err(RDError::PERMISSION_DENIED)
-        }
-        else if response.status() == StatusCode::UNAUTHORIZED {
-            Err(RDError::BAD_TOKEN)
+        if response.status().is_success() {
+            Self::parse::<Vec<TorrentHost>>(response).await
         }
         else {
-            Ok(response.json::<Vec<TorrentHost>>().await.unwrap())
+            Err(RDError::from_response(response).await)
         }
     }
 
     /// Add torrent file.
     async fn add_torrent_file(&self, path: String, host: Option<ParamsTorrentHost>) -> Result<TorrentAdd, RDError> {
         let mut params: String = String::new();
-        if host.is_some() {
-            match host.unwrap() {
-                ParamsTorrentHost::FROM_STRUCT(d) => params.push_str(d.host().as_str()),
-                ParamsTorrentHost::FROM_HOST(d) => params.push_str(d.as_str()),
+        if let Some(host) = host {
+            match host {
+                ParamsTorrentHost::FROM_STRUCT(d) => params.push_str(format!("host={}", d.host()).as_str()),
+                ParamsTorrentHost::FROM_HOST(d) => params.push_str(format!("host={}", d).as_str()),
             }
         }
 
-        if !fs::try_exists(path.clone()).await.unwrap() {
+        if !fs::try_exists(path.clone()).await? {
             return Err(RDError::PATH_NOT_RIGHT);
         }
 
-        let mut file = File::open(path).await.unwrap();
+        let mut file = File::open(path).await?;
         // Read the entire file into a buffer
         let mut buffer = Vec::new();
-        file.read_to_end(&mut buffer).await.unwrap();
+        file.read_to_end(&mut buffer).await?;
+        // Reject corrupt file before upload
+        TorrentMeta::from_bytes(&buffer)?;
 
-        let response = self.client.put(Self::create_link("torrents/addTorrent", Some(params.as_str()))).bearer_auth(self.token.clone()).body(buffer).send().await.unwrap();
+        let response = self.send(self.client.put(self.create_link("torrents/addTorrent", Some(params.as_str()))).body(buffer)).await?;
 
-        if response.status() == StatusCode::FORBIDDEN {
-            Err(RDError::NOT_PREMIUM)
-        }
-        else if response.status() == StatusCode::UNAUTHORIZED {
-            Err(RDError::BAD_TOKEN)
-        }
-        else if response.status() == StatusCode::BAD_REQUEST {
-            Err(RDError::BAD_REQUEST)
-        }
-        else if response.status() == StatusCode::SERVICE_UNAVAILABLE {
-            Err(RDError::SERVICE_UNAVAILABLE)
-        }
-        else if response.status() == StatusCode::CREATED {
-            Ok(response.json::<TorrentAdd>().await.unwrap())
+        if response.status().is_success() {
+            Self::parse::<TorrentAdd>(response).await
         }
         else {
-            Err(RDError::UNDEFINED)
+            Err(RDError::from_response(response).await)
         }
-
     }
 
-    /// Add magnet link.
-    async fn add_torrent_magnet(&self, magnet: String, host: Option<ParamsTorrentHost>) -> Result<TorrentAdd, RDError> {
-
+    /// Add magnet link, from a String or a Magnet.
+    async fn add_torrent_magnet(&self, magnet: impl Into<String>, host: Option<ParamsTorrentHost>) -> Result<TorrentAdd, RDError> {
         let mut params = HashMap::new();
-        params.insert("magnet", magnet);
-
-        if host.is_some() {
-            match host.unwrap() {
+        params.insert("magnet", magnet.into());
+        if let Some(host) = host {
+            match host {
                 ParamsTorrentHost::FROM_STRUCT(d) => params.insert("host", d.host().to_string()),
                 ParamsTorrentHost::FROM_HOST(d) => params.insert("host", d),
             };
         }
 
-        let response = self.client.post(Self::create_link("torrents/addMagnet", None)).form(&params).bearer_auth(self.token.clone()).send().await.unwrap();
+        let response = self.send(self.client.post(self.create_link("torrents/addMagnet", None)).form(&params)).await?;
 
-        if response.status() == StatusCode::FORBIDDEN {
-            Err(RDError::NOT_PREMIUM)
-        }
-        else if response.status() == StatusCode::UNAUTHORIZED {
-            Err(RDError::BAD_TOKEN)
-        }
-        else if response.status() == StatusCode::BAD_REQUEST {
-            Err(RDError::BAD_REQUEST)
-        }
-        else if response.status() == StatusCode::SERVICE_UNAVAILABLE {
-            Err(RDError::SERVICE_UNAVAILABLE)
-        }
-        else if response.status() == StatusCode::CREATED {
-            Ok(response.json::<TorrentAdd>().await.unwrap())
+        if response.status().is_success() {
+            Self::parse::<TorrentAdd>(response).await
         }
         else {
-            Err(RDError::UNDEFINED)
+            Err(RDError::from_response(response).await)
         }
-
     }
 
     /// Select files of a torrent.
-    #[allow(unused_assignments)]
     async fn select_torrent_file(&self, torrent: ParamsTorrent, files: ParamsTorrentFile) -> Result<(),RDError> {
-        let mut id_torrent = String::new();
-        match torrent {
-            ParamsTorrent::FROM_STRUCT(d) => id_torrent = d.id().to_string(),
-            ParamsTorrent::FROM_ADD(d) => id_torrent = d.id().to_string(),
-            ParamsTorrent::FROM_ID(d) => id_torrent = d,
+        let id_torrent = match torrent {
+            ParamsTorrent::FROM_STRUCT(d) => d.id().to_string(),
+            ParamsTorrent::FROM_ADD(d) => d.id().to_string(),
+            ParamsTorrent::FROM_ID(d) => d,
         };
 
         let mut params = HashMap::new();
@@ -724,101 +887,95 @@ impl RDTraitAsync for RDClient {
             ParamsTorrentFile::FROM_IDS(d) => params.insert("files", d.join(",")),
         };
 
-        let response = self.client.post(Self::create_link(format!("torrents/selectFiles/{}", id_torrent).as_str(), None)).bearer_auth(self.token.clone()).form(&params).send().await.unwrap();
+        let response = self.send(self.client.post(self.create_link(format!("torrents/selectFiles/{}", id_torrent).as_str(), None)).form(&params)).await?;
 
-        if response.status() == StatusCode::FORBIDDEN {
-            Err(RDError::NOT_PREMIUM)
-        }
-        else if response.status() == StatusCode::UNAUTHORIZED {
-            Err(RDError::BAD_TOKEN)
-        }
-        else if response.status() == StatusCode::BAD_REQUEST {
-            Err(RDError::BAD_REQUEST)
-        }
-        else if response.status() == StatusCode::NOT_FOUND {
-            Err(RDError::UNKNOWN_RESSOURCE)
-        }
-        else if response.status() == StatusCode::ACCEPTED {
+        if response.status() == StatusCode::ACCEPTED {
             Err(RDError::ACTION_ALREADY_DONE)
         }
-        else if response.status() == StatusCode::NO_CONTENT {
+        else if response.status().is_success() {
             Ok(())
         }
         else {
-            Err(RDError::UNDEFINED)
+            Err(RDError::from_response(response).await)
         }
-
     }
 
     /// Delete a torrent from torrents list.
-    #[allow(unused_assignments)]
     async fn remove_torrent(&self, torrent: ParamsTorrent) -> Result<RDOk, RDError> {
-        let mut id_remove = String::new();
-        match torrent {
-            ParamsTorrent::FROM_STRUCT(d) => id_remove = d.id().to_string(),
-            ParamsTorrent::FROM_ADD(d) => id_remove = d.id().to_string(),
-            ParamsTorrent::FROM_ID(d) => id_remove = d,
-        }
+        let id_remove = match torrent {
+            ParamsTorrent::FROM_STRUCT(d) => d.id().to_string(),
+            ParamsTorrent::FROM_ADD(d) => d.id().to_string(),
+            ParamsTorrent::FROM_ID(d) => d,
+        };
 
-        let response = self.client.delete(Self::create_link(format!("torrent/delete/{}",id_remove).as_str(), None)).bearer_auth(self.token.clone()).send().await.unwrap();
+        let response = self.send(self.client.delete(self.create_link(format!("torrents/delete/{}",id_remove).as_str(), None))).await?;
 
-        if response.status() == StatusCode::FORBIDDEN {
-            Err(RDError::PERMISSION_DENIED)
-        }
-        else if response.status() == StatusCode::UNAUTHORIZED {
-            Err(RDError::BAD_TOKEN)
-        }
-        else if response.status() == StatusCode::NOT_FOUND {
-            Err(RDError::UNKNOWN_RESSOURCE)
-        }
-        else if response.status() == StatusCode::NO_CONTENT  {
+        if response.status() == StatusCode::NO_CONTENT {
             Ok(RDOk::REMOVED_SUCCESS)
         }
         else {
-            println!("{:?}", response.status());
-            Err(RDError::UNDEFINED)
+            Err(RDError::from_response(response).await)
         }
-
     }
 
     /// Get supported hosts.
-    async fn get_host() -> Hosts {
-        let response = reqwest::get(Self::create_link("hosts", None)).await.unwrap();
-        let mut hosts = Hosts::default();
-        hosts.result = response.json::<HashMap<String, Host>>().await.unwrap();
-        hosts.with_status = false;
-        hosts
+    async fn get_host(&self) -> Result<Hosts, RDError> {
+        let response = self.send_public(self.client.get(self.create_link("hosts", None))).await?;
+
+        if response.status().is_success() {
+            Ok(Hosts { result: Self::parse::<HashMap<String, Host>>(response).await?, with_status: false })
+        }
+        else {
+            Err(RDError::from_response(response).await)
+        }
     }
 
     /// Get all supported regex.
-    async fn get_host_regex() -> Vec<String> {
-        reqwest::get(Self::create_link("hosts/regex", None)).await.unwrap().json::<Vec<String>>().await.unwrap()
+    async fn get_host_regex(&self) -> Result<Vec<String>, RDError> {
+        let response = self.send_public(self.client.get(self.create_link("hosts/regex", None))).await?;
+
+        if response.status().is_success() {
+            Self::parse::<Vec<String>>(response).await
+        }
+        else {
+            Err(RDError::from_response(response).await)
+        }
     }
 
     /// Get all supported regex for folder links.
-    async fn get_host_regex_folder() -> Vec<String> {
-        reqwest::get(Self::create_link("hosts/regexFolder", None)).await.unwrap().json::<Vec<String>>().await.unwrap()
+    async fn get_host_regex_folder(&self) -> Result<Vec<String>, RDError> {
+        let response = self.send_public(self.client.get(self.create_link("hosts/regexFolder", None))).await?;
+
+        if response.status().is_success() {
+            Self::parse::<Vec<String>>(response).await
+        }
+        else {
+            Err(RDError::from_response(response).await)
+        }
     }
 
     /// Get all supported domains.
-    async fn get_host_domains() -> Vec<String> {
-        reqwest::get(Self::create_link("hosts/domains", None)).await.unwrap().json::<Vec<String>>().await.unwrap()
+    async fn get_host_domains(&self) -> Result<Vec<String>, RDError> {
+        let response = self.send_public(self.client.get(self.create_link("hosts/domains", None))).await?;
+
+        if response.status().is_success() {
+            Self::parse::<Vec<String>>(response).await
+        }
+        else {
+            Err(RDError::from_response(response).await)
+        }
     }
 
     /// Get status of hosters.
     async fn get_host_with_status(&self) -> Result<Hosts, RDError> {
-        let response = self.client.get(Self::create_link("hosts/status", None)).bearer_auth(self.token.clone()).send().await.unwrap();
+        let response = self.send(self.client.get(self.create_link("hosts/status", None))).await?;
 
-        if response.status() == StatusCode::UNAUTHORIZED {
-            Err(RDError::BAD_TOKEN)
+        if response.status().is_success() {
+            Ok(Hosts { result: Self::parse::<HashMap<String, Host>>(response).await?, with_status: true })
         }
         else {
-            let mut hosts = Hosts::default();
-            hosts.result = response.json::<HashMap<String, Host>>().await.unwrap();
-            hosts.with_status = true;
-            Ok(hosts)
+            Err(RDError::from_response(response).await)
         }
-
     }
 
 }
@@ -826,11 +983,189 @@ impl RDTraitAsync for RDClient {
 
 #[cfg(test)]
 mod tests {
+    use crate::host_matcher::{HostPatterns, LinkKind};
+    use crate::file_selector::{FileRule, FileSelector};
+    use crate::data_struct::torrent::TorrentFile;
+    use crate::torrent_meta::{Bencode, TorrentMeta};
+    use crate::magnet::Magnet;
+    use crate::data_struct::RDError;
+    use crate::data_struct::container::ContainerType;
+    use crate::downloader::DownloadFile;
+    use crate::downloader::journal::Journal;
+    use crate::downloader::queue::TimeWindow;
+    use crate::data_struct::error::{ApiError, ApiErrorBody, ApiErrorCode};
 
     #[tokio::test]
     async fn it_works() {
 
 
 
+    }
+
+    #[test]
+    fn host_patterns_classify() {
+        assert_eq!(HostPatterns::translate("/(https?:\\/\\/)?(www\\.)?1fichier\\.com\\/\\?[a-z0-9]{20}/i"), "(?i)(https?:\\/\\/)?(www\\.)?1fichier\\.com\\/\\?[a-z0-9]{20}");
+
+        let patterns = HostPatterns::compile(
+            &["/(https?:\\/\\/)?(www\\.)?1fichier\\.com\\/\\?[a-z0-9]{20}/".to_string(), "/(?<=broken/".to_string()],
+            &["/(https?:\\/\\/)?(www\\.)?1fichier\\.com\\/dir\\/[a-z0-9]+/".to_string()],
+            &["1fichier.com".to_string()],
+        ).unwrap();
+        assert_eq!(patterns.classify("https://www.1fichier.com/?abcdefghij0123456789"), LinkKind::FILE("1fichier.com".to_string()));
+        assert_eq!(patterns.classify("1fichier.com/dir/abc"), LinkKind::FOLDER("1fichier.com".to_string()));
+        assert_eq!(patterns.classify("https://example.com/file"), LinkKind::UNSUPPORTED);
+        assert_eq!(patterns.classify("https://evil.example/?u=1fichier.com/?abcdefghij0123456789"), LinkKind::UNSUPPORTED);
+        assert_eq!(patterns.classify("https://a.1fichier.com/?abcdefghij0123456789"), LinkKind::FILE("1fichier.com".to_string()));
+    }
+
+    #[test]
+    fn file_selector_select() {
+        let files: Vec<TorrentFile> = serde_json::from_str(r#"[
+            {"id": 1, "path": "/Show.S01E01.1080p.mkv", "bytes": 900, "selected": 0},
+            {"id": 2, "path": "/Show.S01E02.1080p.mkv", "bytes": 1000, "selected": 0},
+            {"id": 3, "path": "/Sample/Show.S01E01.sample.mkv", "bytes": 50, "selected": 0},
+            {"id": 4, "path": "/Show.S01E01.nfo", "bytes": 1, "selected": 0},
+            {"id": 5, "path": "/Extras/Making of.mp4", "bytes": 400, "selected": 0}
+        ]"#).unwrap();
+        let ids = |selector: &FileSelector| selector.select(&files).iter().map(|d| *d.id()).collect::<Vec<_>>();
+
+        assert_eq!(ids(&FileSelector::video()), vec![1, 2]);
+        assert_eq!(ids(&FileSelector::video().episode(1, Some(2))), vec![2]);
+        assert_eq!(ids(&FileSelector::new().largest_only()), vec![2]);
+        assert_eq!(ids(&FileSelector::new().exclude_extensions(&["nfo"]).min_size(100)), vec![1, 2, 5]);
+        assert_eq!(ids(&FileSelector::new().glob("/**/*.mkv").unwrap()), vec![1, 2, 3]);
+        assert_eq!(ids(&FileSelector::new().glob("/**/*.mkv").unwrap().rule(FileRule::NOT(Box::new(FileRule::NO_EXTRAS)))), vec![3]);
+        // Largest only applies in rules of selector and ALL, ANY and NOT ignore it.
+        assert_eq!(ids(&FileSelector::new().rule(FileRule::ALL(vec![FileRule::LARGEST_ONLY, FileRule::MAX_SIZE(950)]))), vec![1]);
+        assert_eq!(ids(&FileSelector::new().rule(FileRule::ANY(vec![FileRule::LARGEST_ONLY, FileRule::EXTENSIONS(vec!["nfo".to_string()])]))), vec![4]);
+        assert_eq!(ids(&FileSelector::new().rule(FileRule::NOT(Box::new(FileRule::LARGEST_ONLY)))), vec![1, 2, 3, 4, 5]);
+        assert_eq!(ids(&FileSelector::new().rule(FileRule::ANY(vec![FileRule::LARGEST_ONLY]))), vec![1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn torrent_meta_parse() {
+        let pieces = "x".repeat(40);
+        let data = format!("d8:announce14:http://tracker4:infod5:filesld6:lengthi100e4:pathl3:dir5:a.mkveed4:attr1:p6:lengthi28e4:pathl4:.pad2:28eed6:lengthi20e4:pathl5:b.nfoeee4:name4:test12:piece lengthi128e6:pieces40:{}ee", pieces);
+        let meta = TorrentMeta::from_bytes(data.as_bytes()).unwrap();
+        assert_eq!(meta.info_hash(), "4fd31a5bbf64bc8f4dfc2ee400220be68f984548");
+        assert_eq!(meta.info_hash_v2(), &None);
+        assert_eq!(meta.files().iter().map(|d| (d.path().as_str(), *d.bytes())).collect::<Vec<_>>(), vec![("/dir/a.mkv", 100), ("/b.nfo", 20)]);
+        assert_eq!(*meta.total_size(), 120);
+        assert_eq!(meta.trackers(), &vec!["http://tracker".to_string()]);
+
+        // Older torrents with names not in utf-8.
+        let latin1 = [b"d4:infod6:lengthi5e4:name5:caf\xe9s12:piece lengthi128e6:pieces20:".as_slice(), "x".repeat(20).as_bytes(), b"ee"].concat();
+        assert_eq!(TorrentMeta::from_bytes(&latin1).unwrap().name(), "caf\u{fffd}s");
+
+        assert!(TorrentMeta::from_bytes(&data.as_bytes()[..data.len() - 1]).is_err());
+        assert!(TorrentMeta::from_bytes(data.replace("lengthi128e", "lengthi64e").as_bytes()).is_err());
+        assert!(Bencode::parse(b"i03e").is_err());
+        assert_eq!(Bencode::parse(b"l4:spami-3ee").unwrap(), Bencode::LIST(vec![Bencode::BYTES(b"spam".to_vec()), Bencode::INT(-3)]));
+    }
+
+    #[test]
+    fn magnet_parse_build() {
+        let magnet = Magnet::parse("magnet:?xt=urn:btih:YNCKHTQCWBTRNJIV4WNAE52SJUQCZO5C&dn=Big+Buck%20Bunny&tr.1=udp%3A%2F%2Ftracker%3A80&xl=42&ws=https://seed/a").unwrap();
+        assert_eq!(magnet.info_hash(), "c344a3ce02b06716a515e59a0277524d202cbba2");
+        assert_eq!(magnet.name().as_deref(), Some("Big Buck Bunny"));
+        assert_eq!(magnet.trackers(), &vec!["udp://tracker:80".to_string()]);
+        assert_eq!(*magnet.size(), Some(42));
+        assert_eq!(magnet.web_seeds(), &vec!["https://seed/a".to_string()]);
+        assert_eq!(Magnet::parse(&magnet.to_string()).unwrap(), magnet);
+        assert_eq!(Magnet::new("C344A3CE02B06716A515E59A0277524D202CBBA2").unwrap().to_string(), "magnet:?xt=urn:btih:c344a3ce02b06716a515e59a0277524d202cbba2");
+
+        assert!(Magnet::parse("magnet:?xt=urn:btih:c3445a3e").is_err());
+        assert!(Magnet::parse("magnet:?dn=name").is_err());
+        assert!(Magnet::parse("http://example.com").is_err());
+    }
+
+    #[test]
+    fn api_error_kept_variants() {
+        let error = |code: i64| RDError::from_api(ApiError::from(ApiErrorBody { error: "error".to_string(), error_code: code, error_details: None }));
+        assert!(matches!(error(8), RDError::BAD_TOKEN));
+        assert!(matches!(error(9), RDError::PERMISSION_DENIED));
+        assert!(matches!(error(7), RDError::UNKNOWN_RESSOURCE));
+        assert!(matches!(error(20), RDError::NOT_PREMIUM));
+        assert!(matches!(error(24), RDError::FILE_UNAVAILABLE));
+        assert!(matches!(error(21), RDError::API(_)));
+        assert_eq!(error(24).api_code(), Some(ApiErrorCode::FILE_UNAVAILABLE));
+        assert_eq!(error(9).api_code(), Some(ApiErrorCode::PERMISSION_DENIED));
+        assert_eq!(error(21).api_code(), Some(ApiErrorCode::TOO_MANY_ACTIVE_DOWNLOADS));
+    }
+
+    #[test]
+    fn container_type_detect() {
+        let hex = b"0a1b2c3d4e5f\n6789abcdef";
+        let base64 = format!("{}==", "QUJD".repeat(30));
+
+        assert_eq!(ContainerType::detect(Some("links.ccf"), b"CCF3.0 data"), Some(ContainerType::CCF3));
+        assert_eq!(ContainerType::detect(Some("links.CCF"), &[0x8f, 0x01, 0x22]), Some(ContainerType::CCF));
+        assert_eq!(ContainerType::detect(Some("links.ccf"), b""), None);
+        assert_eq!(ContainerType::detect(Some("links.rsdf"), hex), Some(ContainerType::RSDF));
+        assert_eq!(ContainerType::detect(Some("links.rsdf"), base64.as_bytes()), None);
+        assert_eq!(ContainerType::detect(Some("links.dlc"), base64.as_bytes()), Some(ContainerType::DLC));
+        assert_eq!(ContainerType::detect(Some("links.dlc"), b"QUJD"), None);
+        assert_eq!(ContainerType::detect(Some("links.txt"), hex), None);
+
+        // Hex text is also base64, RSDF is checked first.
+        assert_eq!(ContainerType::detect(None, hex), Some(ContainerType::RSDF));
+        assert_eq!(ContainerType::detect(None, "0123456789".repeat(10).as_bytes()), Some(ContainerType::RSDF));
+        assert_eq!(ContainerType::detect(None, base64.as_bytes()), Some(ContainerType::DLC));
+        assert_eq!(ContainerType::detect(None, b"CCF3.0 data"), Some(ContainerType::CCF3));
+        assert_eq!(ContainerType::detect(None, &[0x8f, 0x01, 0x22]), None);
+        assert_eq!(ContainerType::detect(None, b"   "), None);
+    }
+
+    #[test]
+    fn journal_ranges() {
+        let mut journal = Journal::new(DownloadFile::from_url("http://host/file".to_string()), 100);
+        assert_eq!(journal.missing(), vec![(0, 100)]);
+
+        journal.add(10, 20);
+        journal.add(40, 50);
+        journal.add(30, 30);
+        assert_eq!(journal.done_bytes(), 20);
+        assert_eq!(journal.missing(), vec![(0, 10), (20, 40), (50, 100)]);
+
+        // Adjacent and overlapping ranges are merged.
+        journal.add(20, 25);
+        journal.add(45, 60);
+        journal.add(5, 12);
+        assert_eq!(journal.done_bytes(), 40);
+        assert_eq!(journal.missing(), vec![(0, 5), (25, 40), (60, 100)]);
+
+        journal.add(0, 100);
+        assert_eq!(journal.done_bytes(), 100);
+        assert!(journal.missing().is_empty());
+
+        journal.reset();
+        assert_eq!(journal.missing(), vec![(0, 100)]);
+    }
+
+    #[test]
+    fn time_window_contains() {
+        let night = TimeWindow::new((1, 0), (7, 0)).unwrap();
+        assert!(!night.contains(59));
+        assert!(night.contains(60));
+        assert!(night.contains(7 * 60 - 1));
+        assert!(!night.contains(7 * 60));
+
+        let midnight = TimeWindow::new((22, 30), (6, 0)).unwrap();
+        assert!(midnight.contains(23 * 60));
+        assert!(midnight.contains(0));
+        assert!(midnight.contains(5 * 60 + 59));
+        assert!(!midnight.contains(6 * 60));
+        assert!(!midnight.contains(22 * 60 + 29));
+
+        let evening = TimeWindow::new((18, 0), (24, 0)).unwrap();
+        assert!(evening.contains(23 * 60 + 59));
+        assert!(!evening.contains(0));
+        assert!(TimeWindow::new((0, 0), (24, 0)).unwrap().contains(0));
+        assert!(TimeWindow::new((0, 0), (24, 0)).unwrap().contains(23 * 60 + 59));
+
+        assert!(matches!(TimeWindow::new((24, 0), (6, 0)), Err(RDError::INVALID_TIME_WINDOW)));
+        assert!(matches!(TimeWindow::new((1, 60), (6, 0)), Err(RDError::INVALID_TIME_WINDOW)));
+        assert!(matches!(TimeWindow::new((1, 0), (24, 30)), Err(RDError::INVALID_TIME_WINDOW)));
+        assert!(matches!(TimeWindow::new((6, 0), (6, 0)), Err(RDError::INVALID_TIME_WINDOW)));
     }
 }
@@ -0,0 +1,169 @@
+use std::fmt;
+use std::str::FromStr;
+use getset::Getters;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use crate::data_struct::RDError;
+use crate::data_struct::torrent::Torrent;
+
+const PREFIX: &str = "magnet:?";
+const BTIH: &str = "urn:btih:";
+const BASE32_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+/// Characters kept as is in values of built links.
+const VALUE: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'.').remove(b'_').remove(b'~').remove(b':').remove(b'/');
+
+/// Magnet link of a BitTorrent v1 info-hash.
+#[derive(Debug, Clone, PartialEq, Eq, Getters)]
+pub struct Magnet {
+    /// Lowercase hex, like Torrent::hash.
+    #[getset(get = "pub")]
+    info_hash: String,
+    /// Display name (dn).
+    #[getset(get = "pub")]
+    name: Option<String>,
+    /// Trackers (tr).
+    #[getset(get = "pub")]
+    trackers: Vec<String>,
+    /// Bytes (xl).
+    #[getset(get = "pub")]
+    size: Option<u64>,
+    /// Web seeds (ws).
+    #[getset(get = "pub")]
+    web_seeds: Vec<String>,
+}
+
+impl Magnet {
+
+    /// Magnet of an info-hash, in hex (40 characters) or base32 (32 characters).
+    pub fn new(info_hash: &str) -> Result<Magnet, RDError> {
+        Ok(Magnet { info_hash: normalize_hash(info_hash)?, name: None, trackers: Vec::new(), size: None, web_seeds: Vec::new() })
+    }
+
+    /// Magnet of a torrent of account, with filename and size.
+    pub fn from_torrent(torrent: &Torrent) -> Result<Magnet, RDError> {
+        Ok(Magnet::new(torrent.hash())?.with_name(torrent.filename()).with_size(*torrent.bytes()))
+    }
+
+    /// Parse a magnet link, other parameters and exact topics are ignored.
+    pub fn parse(link: &str) -> Result<Magnet, RDError> {
+        let query = link.get(..PREFIX.len())
+            .filter(|prefix| prefix.eq_ignore_ascii_case(PREFIX))
+            .map(|_| &link[PREFIX.len()..])
+            .ok_or_else(|| invalid("missing magnet:? prefix"))?;
+
+        let mut magnet: Option<Magnet> = None;
+        let mut name = None;
+        let mut trackers = Vec::new();
+        let mut size = None;
+        let mut web_seeds = Vec::new();
+        for (key, value) in query.split('&').filter(|d| !d.is_empty()).filter_map(|d| d.split_once('=')) {
+            let value = percent_decode_str(&value.replace('+', " ")).decode_utf8().map_err(|_| invalid("value is not utf-8"))?.into_owned();
+            // Keys may be numbered like tr.1
+            match key.split('.').next().unwrap_or(key) {
+                "xt" => {
+                    if let Some(hash) = value.get(..BTIH.len()).filter(|d| d.eq_ignore_ascii_case(BTIH)).map(|_| &value[BTIH.len()..]) {
+                        magnet = Some(Magnet::new(hash)?);
+                    }
+                },
+                "dn" => name = Some(value),
+                "tr" => trackers.push(value),
+                "xl" => size = Some(value.parse::<u64>().map_err(|_| invalid("invalid xl"))?),
+                "ws" => web_seeds.push(value),
+                _ => {},
+            }
+        }
+
+        let mut magnet = magnet.ok_or_else(|| invalid("missing xt=urn:btih"))?;
+        magnet.name = name;
+        magnet.trackers = trackers;
+        magnet.size = size;
+        magnet.web_seeds = web_seeds;
+        Ok(magnet)
+    }
+
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    pub fn with_tracker(mut self, tracker: &str) -> Self {
+        self.trackers.push(tracker.to_string());
+        self
+    }
+
+    pub fn with_size(mut self, bytes: u64) -> Self {
+        self.size = Some(bytes);
+        self
+    }
+
+    pub fn with_web_seed(mut self, url: &str) -> Self {
+        self.web_seeds.push(url.to_string());
+        self
+    }
+
+}
+
+impl fmt::Display for Magnet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}xt={}{}", PREFIX, BTIH, self.info_hash)?;
+        if let Some(name) = &self.name {
+            write!(f, "&dn={}", utf8_percent_encode(name, VALUE))?;
+        }
+        if let Some(size) = self.size {
+            write!(f, "&xl={}", size)?;
+        }
+        for tracker in &self.trackers {
+            write!(f, "&tr={}", utf8_percent_encode(tracker, VALUE))?;
+        }
+        for web_seed in &self.web_seeds {
+            write!(f, "&ws={}", utf8_percent_encode(web_seed, VALUE))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Magnet {
+    type Err = RDError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Magnet::parse(s)
+    }
+}
+
+impl From<Magnet> for String {
+    fn from(value: Magnet) -> Self {
+        value.to_string()
+    }
+}
+
+impl From<&Magnet> for String {
+    fn from(value: &Magnet) -> Self {
+        value.to_string()
+    }
+}
+
+fn invalid(message: &str) -> RDError {
+    RDError::INVALID_MAGNET(message.to_string())
+}
+
+/// Hex or base32 info-hash to lowercase hex.
+fn normalize_hash(hash: &str) -> Result<String, RDError> {
+    match hash.len() {
+        40 if hash.bytes().all(|d| d.is_ascii_hexdigit()) => Ok(hash.to_ascii_lowercase()),
+        32 => {
+            let mut bytes = Vec::with_capacity(20);
+            let mut buffer = 0u64;
+            let mut bits = 0;
+            for c in hash.bytes() {
+                let value = BASE32_ALPHABET.iter().position(|d| *d == c.to_ascii_uppercase()).ok_or_else(|| invalid("invalid base32 info-hash"))?;
+                buffer = (buffer << 5) | value as u64;
+                bits += 5;
+                if bits >= 8 {
+                    bits -= 8;
+                    bytes.push((buffer >> bits) as u8);
+                }
+            }
+            Ok(bytes.iter().map(|d| format!("{:02x}", d)).collect())
+        },
+        _ => Err(invalid("info-hash must be 40 hex or 32 base32 characters")),
+    }
+}
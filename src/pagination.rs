@@ -0,0 +1,136 @@
+use std::future::{ready, Future};
+use futures::stream::{self, BoxStream, Stream, StreamExt, TryStreamExt};
+use crate::{RDClient, RDTraitAsync};
+use crate::data_struct::RDError;
+use crate::data_struct::download::Download;
+use crate::data_struct::torrent::Torrent;
+
+/// Real-Debrid refuse more than 5000 entries per page.
+pub const MAX_PAGE_SIZE: u32 = 5000;
+
+/// Paging of list streams.
+#[derive(Debug, Clone)]
+pub struct PageOptions {
+    page_size: u32,
+    concurrency: usize,
+    filter: Option<String>,
+}
+
+impl Default for PageOptions {
+    fn default() -> Self {
+        PageOptions { page_size: 100, concurrency: 2, filter: None }
+    }
+}
+
+impl PageOptions {
+
+    /// Pages of 100 entries, 2 pages fetched at once.
+    pub fn new() -> PageOptions {
+        PageOptions::default()
+    }
+
+    /// Entries per request, between 1 and 5000.
+    pub fn page_size(mut self, page_size: u32) -> Self {
+        self.page_size = page_size.clamp(1, MAX_PAGE_SIZE);
+        self
+    }
+
+    /// Pages fetched at once after first page.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Filter of torrents list ("active" for active torrents only).
+    pub fn filter(mut self, filter: impl Into<String>) -> Self {
+        self.filter = Some(filter.into());
+        self
+    }
+
+}
+
+impl RDClient {
+
+    /// Stream every download of user, in api order.
+    pub fn downloads_stream(&self, options: PageOptions) -> impl Stream<Item = Result<Download, RDError>> + Send + 'static {
+        let client = self.clone();
+        let page_size = options.page_size;
+        paginate(options, move |page| {
+            let client = client.clone();
+            async move {
+                let downloads = client.get_downloads(None, Some(page), Some(page_size)).await?;
+                Ok((downloads.result, downloads.total_count))
+            }
+        })
+    }
+
+    /// Stream every torrent of user, in api order.
+    pub fn torrents_stream(&self, options: PageOptions) -> impl Stream<Item = Result<Torrent, RDError>> + Send + 'static {
+        let client = self.clone();
+        let page_size = options.page_size;
+        let filter = options.filter.clone();
+        paginate(options, move |page| {
+            let client = client.clone();
+            let filter = filter.clone();
+            async move {
+                let torrents = client.get_torrents(None, Some(page), Some(page_size), filter).await?;
+                Ok((torrents.result, torrents.total_count))
+            }
+        })
+    }
+
+    /// Get every download of user.
+    pub async fn get_all_downloads(&self, options: PageOptions) -> Result<Vec<Download>, RDError> {
+        collect_all(self.downloads_stream(options)).await
+    }
+
+    /// Get every torrent of user.
+    pub async fn get_all_torrents(&self, options: PageOptions) -> Result<Vec<Torrent>, RDError> {
+        collect_all(self.torrents_stream(options)).await
+    }
+
+}
+
+/// Collect a stream, stop at first error.
+pub async fn collect_all<T>(stream: impl Stream<Item = Result<T, RDError>>) -> Result<Vec<T>, RDError> {
+    stream.try_collect().await
+}
+
+/// Fetch first page to know total count, then next pages with concurrency.
+/// An empty list (NO_CONTENT) end the stream.
+fn paginate<T, F, Fut>(options: PageOptions, fetch: F) -> impl Stream<Item = Result<T, RDError>> + Send + 'static
+where
+    T: Send + 'static,
+    F: Fn(u32) -> Fut + Clone + Send + 'static,
+    Fut: Future<Output = Result<(Vec<T>, u64), RDError>> + Send + 'static,
+{
+    let fetch = move |page: u32| {
+        let result = fetch(page);
+        async move {
+            match result.await {
+                Err(RDError::NO_CONTENT) => Ok((Vec::new(), 0)),
+                result => result,
+            }
+        }
+    };
+
+    stream::once(fetch(1))
+        .map(move |first| -> BoxStream<'static, Result<Vec<T>, RDError>> {
+            match first {
+                Ok((items, total_count)) => {
+                    let pages = total_count.div_ceil(options.page_size as u64).min(u32::MAX as u64) as u32;
+                    let next = stream::iter(2..=pages)
+                        .map(fetch.clone())
+                        .buffered(options.concurrency)
+                        .map_ok(|(items, _)| items);
+                    stream::once(ready(Ok(items))).chain(next).boxed()
+                },
+                Err(e) => stream::once(ready(Err(e))).boxed(),
+            }
+        })
+        .flatten()
+        .flat_map(|page| match page {
+            Ok(items) => stream::iter(items.into_iter().map(Ok)).left_stream(),
+            Err(e) => stream::once(ready(Err(e))).right_stream(),
+        })
+}
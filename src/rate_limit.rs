@@ -0,0 +1,134 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Real-Debrid allow 250 requests per minute.
+pub const DEFAULT_REQUESTS: u32 = 250;
+pub const DEFAULT_PERIOD: Duration = Duration::from_secs(60);
+
+/// Sliding window limiting number of requests, never more than requests in any period.
+/// Clones share the same window.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    window: Arc<Mutex<Window>>,
+}
+
+#[derive(Debug)]
+struct Window {
+    requests: usize,
+    period: Duration,
+    /// Time of requests allowed during last period, oldest first.
+    sent: VecDeque<Instant>,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        RateLimiter::new(DEFAULT_REQUESTS, DEFAULT_PERIOD)
+    }
+}
+
+impl RateLimiter {
+
+    /// Allow requests per period, with burst up to requests.
+    pub fn new(requests: u32, period: Duration) -> RateLimiter {
+        let requests = requests.max(1) as usize;
+        RateLimiter { window: Arc::new(Mutex::new(Window { requests, period, sent: VecDeque::with_capacity(requests) })) }
+    }
+
+    /// Wait until a request is allowed.
+    pub async fn acquire(&self) {
+        loop {
+            let wait = match self.window().take() {
+                Ok(()) => return,
+                Err(wait) => wait,
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Take a request without waiting, false when limit is reached.
+    pub fn try_acquire(&self) -> bool {
+        self.window().take().is_ok()
+    }
+
+    fn window(&self) -> std::sync::MutexGuard<'_, Window> {
+        self.window.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+}
+
+impl Window {
+
+    /// Count a request now, or time to wait until oldest request leaves window.
+    fn take(&mut self) -> Result<(), Duration> {
+        let now = Instant::now();
+        while self.sent.front().is_some_and(|sent| now.duration_since(*sent) >= self.period) {
+            self.sent.pop_front();
+        }
+        match self.sent.front() {
+            Some(oldest) if self.sent.len() >= self.requests => Err(self.period - now.duration_since(*oldest)),
+            _ => {
+                self.sent.push_back(now);
+                Ok(())
+            },
+        }
+    }
+
+}
+
+/// Limiters used by a RDClient.
+/// Endpoint limiters apply on top of global limiter to requests whose path start with endpoint.
+#[derive(Debug, Clone)]
+pub(crate) struct RateLimits {
+    pub(crate) global: Option<RateLimiter>,
+    pub(crate) endpoints: Vec<(String, RateLimiter)>,
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        RateLimits { global: Some(RateLimiter::default()), endpoints: Vec::new() }
+    }
+}
+
+impl RateLimits {
+
+    pub(crate) async fn acquire(&self, endpoint: &str) {
+        if let Some(global) = &self.global {
+            global.acquire().await;
+        }
+        if let Some((_, limiter)) = self.endpoints.iter().filter(|(prefix, _)| endpoint.starts_with(prefix.as_str())).max_by_key(|(prefix, _)| prefix.len()) {
+            limiter.acquire().await;
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn rate_limiter_window() {
+        let limiter = RateLimiter::default();
+        let mut sent = Vec::new();
+        for _ in 0..(DEFAULT_REQUESTS * 3) {
+            limiter.acquire().await;
+            sent.push(Instant::now());
+        }
+        // Request n and n + 250 are never in the same minute.
+        let requests = DEFAULT_REQUESTS as usize;
+        assert!(sent.windows(requests + 1).all(|d| d[requests].duration_since(d[0]) >= DEFAULT_PERIOD));
+        assert!(sent[requests - 1].duration_since(sent[0]) < Duration::from_secs(1));
+
+        let limiter = RateLimiter::new(2, Duration::from_secs(10));
+        assert!(limiter.try_acquire());
+        tokio::time::advance(Duration::from_secs(6)).await;
+        assert!(limiter.try_acquire());
+        assert!(!limiter.try_acquire());
+        tokio::time::advance(Duration::from_secs(4)).await;
+        assert!(limiter.try_acquire());
+        assert!(!limiter.try_acquire());
+    }
+
+}
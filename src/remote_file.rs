@@ -0,0 +1,312 @@
+use std::collections::VecDeque;
+use std::io::{self, SeekFrom};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use bytes::{Bytes, BytesMut};
+use futures::future::BoxFuture;
+use reqwest::{Client, Response, StatusCode};
+use reqwest::header::{CONTENT_RANGE, RANGE};
+use tokio::io::{AsyncRead, AsyncSeek, ReadBuf};
+use crate::RDClient;
+use crate::data_struct::RDError;
+use crate::downloader::DownloadFile;
+
+pub const DEFAULT_BLOCK_SIZE: u64 = 256 * 1024;
+pub const DEFAULT_CACHE_BLOCKS: usize = 32;
+pub const DEFAULT_READ_AHEAD: u64 = 3;
+
+/// Blocks fetched by one range request.
+type Fetch = BoxFuture<'static, Result<Vec<(u64, Bytes)>, RDError>>;
+
+/// Random access to a generated link with range requests.
+/// Each request fetch the missing block and following read-ahead blocks, recent blocks are kept in a LRU cache.
+pub struct RemoteFile {
+    client: RDClient,
+    url: String,
+    size: u64,
+    position: u64,
+    block_size: u64,
+    read_ahead: u64,
+    cache_blocks: usize,
+    /// Least recently used first.
+    cache: VecDeque<(u64, Bytes)>,
+    pending: Option<(u64, Fetch)>,
+}
+
+impl std::fmt::Debug for RemoteFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RemoteFile")
+            .field("url", &self.url)
+            .field("size", &self.size)
+            .field("position", &self.position)
+            .field("block_size", &self.block_size)
+            .field("read_ahead", &self.read_ahead)
+            .field("cache_blocks", &self.cache_blocks)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RemoteFile {
+
+    /// Bytes, from filesize or Content-Length.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Bytes of a block (default : 256 KiB), clear cache.
+    pub fn block_size(mut self, block_size: u64) -> Self {
+        self.block_size = block_size.max(1);
+        self.cache.clear();
+        self.pending = None;
+        self
+    }
+
+    /// Blocks fetched after a missing block (default : 3).
+    pub fn read_ahead(mut self, blocks: u64) -> Self {
+        self.read_ahead = blocks;
+        self
+    }
+
+    /// Blocks kept in cache (default : 32).
+    pub fn cache_blocks(mut self, blocks: usize) -> Self {
+        self.cache_blocks = blocks.max(1);
+        while self.cache.len() > self.cache_blocks {
+            self.cache.pop_front();
+        }
+        self
+    }
+
+    /// Cached block, marked as recently used.
+    fn cached(&mut self, index: u64) -> Option<Bytes> {
+        let position = self.cache.iter().position(|(i, _)| *i == index)?;
+        let entry = self.cache.remove(position)?;
+        let block = entry.1.clone();
+        self.cache.push_back(entry);
+        Some(block)
+    }
+
+    fn insert(&mut self, index: u64, block: Bytes) {
+        self.cache.retain(|(i, _)| *i != index);
+        self.cache.push_back((index, block));
+        while self.cache.len() > self.cache_blocks {
+            self.cache.pop_front();
+        }
+    }
+
+    fn fetch(&self, index: u64) -> Fetch {
+        let block_size = self.block_size;
+        let start = index * block_size;
+        let end = self.size.min(start.saturating_add(block_size.saturating_mul(self.read_ahead + 1)));
+        let request = self.client.client.get(self.url.as_str()).header(RANGE, format!("bytes={}-{}", start, end - 1));
+        let bandwidth = self.client.bandwidth.clone();
+
+        Box::pin(async move {
+            let response = request.send().await?;
+            let body = match response.status() {
+                StatusCode::PARTIAL_CONTENT => {
+                    if range_start(&response) != Some(start) {
+                        return Err(RDError::RANGE_MISMATCH);
+                    }
+                    read_body(response, 0, end - start, true).await?
+                },
+                // Server ignored range, whole file is sent.
+                StatusCode::OK => read_body(response, start, end - start, false).await?,
+                status => return Err(RDError::from_status(status)),
+            };
+            if let Some(limiter) = bandwidth {
+                limiter.acquire(body.len() as u64).await;
+            }
+
+            Ok((0..body.len()).step_by(block_size as usize)
+                .map(|offset| (index + offset as u64 / block_size, body.slice(offset..body.len().min(offset + block_size as usize))))
+                .collect())
+        })
+    }
+
+}
+
+/// Start of Content-Range like bytes 0-99/150.
+fn range_start(response: &Response) -> Option<u64> {
+    let value = response.headers().get(CONTENT_RANGE)?.to_str().ok()?;
+    let (start, _) = value.strip_prefix("bytes ")?.split_once('-')?;
+    start.trim().parse().ok()
+}
+
+/// Read length bytes of body after skip bytes, without keeping other bytes.
+/// Body must end there when exact, else rest is not read.
+async fn read_body(mut response: Response, mut skip: u64, length: u64, exact: bool) -> Result<Bytes, RDError> {
+    let mut body = BytesMut::with_capacity(length as usize);
+    while let Some(mut chunk) = response.chunk().await? {
+        if skip > 0 {
+            let skipped = skip.min(chunk.len() as u64);
+            chunk = chunk.slice(skipped as usize..);
+            skip -= skipped;
+        }
+        let wanted = (length - body.len() as u64) as usize;
+        if chunk.len() > wanted {
+            if exact {
+                return Err(RDError::SIZE_MISMATCH { expected: length, actual: (body.len() + chunk.len()) as u64 });
+            }
+            body.extend_from_slice(&chunk[..wanted]);
+        }
+        else {
+            body.extend_from_slice(&chunk);
+        }
+        if !exact && body.len() as u64 == length {
+            break;
+        }
+    }
+    if (body.len() as u64) < length {
+        return Err(RDError::SIZE_MISMATCH { expected: length, actual: body.len() as u64 });
+    }
+    Ok(body.freeze())
+}
+
+impl AsyncRead for RemoteFile {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if this.position >= this.size || buf.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+
+        let index = this.position / this.block_size;
+        loop {
+            if let Some(block) = this.cached(index) {
+                let offset = (this.position - index * this.block_size) as usize;
+                if offset >= block.len() {
+                    return Poll::Ready(Err(io::Error::from(io::ErrorKind::UnexpectedEof)));
+                }
+                let length = buf.remaining().min(block.len() - offset);
+                buf.put_slice(&block[offset..offset + length]);
+                this.position += length as u64;
+                return Poll::Ready(Ok(()));
+            }
+
+            if this.pending.as_ref().map(|(i, _)| *i) != Some(index) {
+                this.pending = Some((index, this.fetch(index)));
+            }
+            let Some((_, fetch)) = this.pending.as_mut() else {
+                continue;
+            };
+            let result = match fetch.as_mut().poll(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(result) => result,
+            };
+            this.pending = None;
+            match result {
+                // Asked block last, so it stay in cache when read-ahead exceed cache.
+                Ok(blocks) => blocks.into_iter().rev().for_each(|(i, block)| this.insert(i, block)),
+                Err(e) => return Poll::Ready(Err(io::Error::other(e))),
+            }
+        }
+    }
+}
+
+impl AsyncSeek for RemoteFile {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        let this = self.get_mut();
+        let position = match position {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => this.size.checked_add_signed(offset),
+            SeekFrom::Current(offset) => this.position.checked_add_signed(offset),
+        };
+        this.position = position.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "seek before start of file"))?;
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        Poll::Ready(Ok(self.position))
+    }
+}
+
+impl RDClient {
+
+    /// Size from Content-Range of a request of first byte.
+    async fn range_size(client: &Client, url: &str) -> Result<Option<u64>, RDError> {
+        let response = client.get(url).header(RANGE, "bytes=0-0").send().await?;
+        if !response.status().is_success() {
+            return Err(RDError::from_status(response.status()));
+        }
+        Ok(response.headers().get(CONTENT_RANGE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.rsplit_once('/'))
+            .and_then(|(_, total)| total.parse().ok())
+            .filter(|total| *total != 0))
+    }
+
+    /// Open a generated link for random access without downloading it.
+    /// Fail with UNKNOWN_SIZE when size is not in file nor sent by server.
+    pub async fn open(&self, file: impl Into<DownloadFile>) -> Result<RemoteFile, RDError> {
+        let file = file.into();
+        let size = match *file.filesize() {
+            0 => self.downloader().probe_size(file.url()).await?,
+            size => size,
+        };
+        let size = match size {
+            0 => Self::range_size(&self.client, file.url()).await?.ok_or(RDError::UNKNOWN_SIZE)?,
+            size => size,
+        };
+
+        Ok(RemoteFile {
+            client: self.clone(),
+            url: file.url().clone(),
+            size,
+            position: 0,
+            block_size: DEFAULT_BLOCK_SIZE,
+            read_ahead: DEFAULT_READ_AHEAD,
+            cache_blocks: DEFAULT_CACHE_BLOCKS,
+            cache: VecDeque::new(),
+            pending: None,
+        })
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cached(file: &RemoteFile) -> Vec<u64> {
+        file.cache.iter().map(|(index, _)| *index).collect()
+    }
+
+    #[test]
+    fn remote_file_cache() {
+        let mut file = RemoteFile {
+            client: RDClient::default(),
+            url: String::new(),
+            size: 100,
+            position: 0,
+            block_size: 10,
+            read_ahead: 0,
+            cache_blocks: 3,
+            cache: VecDeque::new(),
+            pending: None,
+        };
+        for index in 0..3 {
+            file.insert(index, Bytes::from(vec![index as u8; 10]));
+        }
+        assert_eq!(cached(&file), [0, 1, 2]);
+
+        // Used block is kept, least recently used one is evicted.
+        assert_eq!(file.cached(0), Some(Bytes::from(vec![0; 10])));
+        file.insert(3, Bytes::from(vec![3; 10]));
+        assert_eq!(cached(&file), [2, 0, 3]);
+        assert_eq!(file.cached(1), None);
+
+        // Block fetched again replaces old one.
+        file.insert(2, Bytes::from(vec![9; 10]));
+        assert_eq!(cached(&file), [0, 3, 2]);
+        assert_eq!(file.cached(2), Some(Bytes::from(vec![9; 10])));
+
+        let file = file.cache_blocks(1);
+        assert_eq!(cached(&file), [2]);
+        let file = file.block_size(20);
+        assert!(file.cache.is_empty());
+    }
+
+}
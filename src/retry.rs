@@ -0,0 +1,149 @@
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use rand::{thread_rng, Rng};
+use reqwest::Method;
+use crate::data_struct::RDError;
+
+/// Retry of requests failing with a transient error.
+/// Only requests with an idempotent method are retried.
+#[derive(Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    jitter: bool,
+    methods: Vec<Method>,
+    retry_if: Arc<dyn Fn(&RDError) -> bool + Send + Sync>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            jitter: true,
+            methods: vec![Method::GET, Method::HEAD, Method::OPTIONS, Method::DELETE],
+            retry_if: Arc::new(RDError::is_transient),
+        }
+    }
+}
+
+impl fmt::Debug for RetryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("max_attempts", &self.max_attempts)
+            .field("base_delay", &self.base_delay)
+            .field("max_delay", &self.max_delay)
+            .field("jitter", &self.jitter)
+            .field("methods", &self.methods)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RetryPolicy {
+
+    /// 3 attempts, delay from 500ms to 30s with jitter, on transient errors of idempotent requests.
+    pub fn new() -> RetryPolicy {
+        RetryPolicy::default()
+    }
+
+    /// Number of attempts including first one.
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Delay before first retry, doubled on each retry.
+    pub fn base_delay(mut self, delay: Duration) -> Self {
+        self.base_delay = delay;
+        self
+    }
+
+    pub fn max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = delay;
+        self
+    }
+
+    /// Wait a random delay between half and full delay.
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Http methods safe to retry (default : GET, HEAD, OPTIONS, DELETE).
+    /// PUT is not retried by default, torrents/addTorrent would add the torrent again.
+    pub fn methods(mut self, methods: Vec<Method>) -> Self {
+        self.methods = methods;
+        self
+    }
+
+    /// Errors to retry (default : RDError::is_transient).
+    pub fn retry_if(mut self, retry_if: impl Fn(&RDError) -> bool + Send + Sync + 'static) -> Self {
+        self.retry_if = Arc::new(retry_if);
+        self
+    }
+
+    /// Check if a new attempt is possible after given attempt.
+    pub(crate) fn allows(&self, method: &Method, attempt: u32) -> bool {
+        attempt < self.max_attempts && self.methods.contains(method)
+    }
+
+    pub(crate) fn should_retry(&self, error: &RDError) -> bool {
+        (self.retry_if)(error)
+    }
+
+    /// Delay before attempt following given attempt.
+    pub(crate) fn delay(&self, attempt: u32) -> Duration {
+        let delay = self.base_delay.saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1))).min(self.max_delay);
+        if self.jitter && !delay.is_zero() {
+            thread_rng().gen_range(delay / 2..=delay)
+        }
+        else {
+            delay
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::StatusCode;
+    use super::*;
+
+    #[test]
+    fn retry_delay() {
+        let policy = RetryPolicy::new().jitter(false).base_delay(Duration::from_secs(1)).max_delay(Duration::from_secs(5));
+        let delays = (1..=5).map(|attempt| policy.delay(attempt)).collect::<Vec<_>>();
+        assert_eq!(delays, [1, 2, 4, 5, 5].map(Duration::from_secs));
+        assert_eq!(policy.delay(u32::MAX), Duration::from_secs(5));
+
+        let policy = policy.jitter(true);
+        for attempt in 1..=5 {
+            let delay = policy.delay(attempt);
+            assert!(delay >= delays[attempt as usize - 1] / 2 && delay <= delays[attempt as usize - 1]);
+        }
+        assert_eq!(RetryPolicy::new().base_delay(Duration::ZERO).delay(1), Duration::ZERO);
+    }
+
+    #[test]
+    fn retry_allows() {
+        let policy = RetryPolicy::new();
+        assert!(policy.allows(&Method::GET, 1));
+        assert!(policy.allows(&Method::DELETE, 2));
+        assert!(!policy.allows(&Method::GET, 3));
+        assert!(!policy.allows(&Method::POST, 1));
+        assert!(!policy.allows(&Method::PUT, 1));
+
+        let policy = policy.methods(vec![Method::PUT]).max_attempts(0);
+        assert!(!policy.allows(&Method::PUT, 1));
+        assert!(policy.max_attempts(2).allows(&Method::PUT, 1));
+
+        let policy = RetryPolicy::new();
+        assert!(policy.should_retry(&RDError::SERVICE_UNAVAILABLE));
+        assert!(!policy.should_retry(&RDError::BAD_TOKEN));
+        assert!(!policy.retry_if(|e| matches!(e, RDError::HTTP_STATUS(StatusCode::BAD_GATEWAY))).should_retry(&RDError::SERVICE_UNAVAILABLE));
+    }
+
+}
@@ -0,0 +1,53 @@
+use std::sync::RwLock;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use crate::data_struct::auth::AuthRefresh;
+
+/// Refresh token this long before it expires.
+pub(crate) const REFRESH_MARGIN: Duration = Duration::from_secs(60);
+
+/// Token shared by all clones of a RDClient.
+#[derive(Debug, Default)]
+pub(crate) struct Session {
+    state: RwLock<SessionState>,
+    /// Held while a refresh is running so only one clone refresh at a time.
+    pub(crate) refresh_lock: Mutex<()>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SessionState {
+    pub(crate) token: String,
+    pub(crate) refresh_authorization: Option<AuthRefresh>,
+}
+
+impl Session {
+
+    pub(crate) fn new(token: String, refresh_authorization: Option<AuthRefresh>) -> Session {
+        Session { state: RwLock::new(SessionState { token, refresh_authorization }), refresh_lock: Mutex::new(()) }
+    }
+
+    pub(crate) fn state(&self) -> SessionState {
+        self.state.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub(crate) fn token(&self) -> String {
+        self.state.read().unwrap_or_else(|e| e.into_inner()).token.clone()
+    }
+
+    pub(crate) fn set(&self, state: SessionState) {
+        *self.state.write().unwrap_or_else(|e| e.into_inner()) = state;
+    }
+
+    pub(crate) fn set_token(&self, token: String) {
+        self.state.write().unwrap_or_else(|e| e.into_inner()).token = token;
+    }
+
+    /// Time left before access token expire, None when not oauth2.
+    pub(crate) fn expires_in(&self) -> Option<Duration> {
+        let state = self.state.read().unwrap_or_else(|e| e.into_inner());
+        let auth_refresh = state.refresh_authorization.as_ref()?;
+        let elapsed = auth_refresh.auth_time.elapsed().unwrap_or_default();
+        Some(Duration::from_secs(auth_refresh.expires_in).saturating_sub(elapsed))
+    }
+
+}
@@ -0,0 +1,85 @@
+use std::fs::OpenOptions;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use tokio::fs;
+use crate::data_struct::RDError;
+
+/// State of a queue saved as json at path.
+#[derive(Debug)]
+pub(crate) struct StateFile<T> {
+    path: PathBuf,
+    state: Mutex<T>,
+    /// Saves are written one at a time.
+    save_lock: tokio::sync::Mutex<()>,
+}
+
+impl<T: Serialize + DeserializeOwned + Default> StateFile<T> {
+
+    /// Load state saved at path, default state when missing.
+    pub(crate) async fn open(path: &Path) -> Result<StateFile<T>, RDError> {
+        let state = load(path).await?.unwrap_or_default();
+        Ok(StateFile { path: path.to_path_buf(), state: Mutex::new(state), save_lock: tokio::sync::Mutex::new(()) })
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub(crate) fn lock(&self) -> MutexGuard<'_, T> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub(crate) async fn save(&self) -> Result<(), RDError> {
+        let _guard = self.save_lock.lock().await;
+        let data = serde_json::to_vec_pretty(&*self.lock())?;
+        write_atomic(&self.path, data).await
+    }
+
+}
+
+/// Json value saved at path, None when missing.
+pub(crate) async fn load<T: DeserializeOwned>(path: &Path) -> Result<Option<T>, RDError> {
+    match fs::read(path).await {
+        Ok(data) => Ok(Some(serde_json::from_slice(&data)?)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Replace file at path with data, written to a temporary file then renamed so it is never left half written.
+/// Only owner can read file when private, even if temporary file was left by another process.
+pub(crate) fn write_atomic_blocking(path: &Path, data: &[u8], private: bool) -> Result<(), RDError> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    if private {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options.open(&tmp)?;
+    // Mode only applies when file is created.
+    #[cfg(unix)]
+    if private {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    }
+    #[cfg(not(unix))]
+    let _ = private;
+    file.write_all(data)?;
+    file.sync_all()?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+pub(crate) async fn write_atomic(path: &Path, data: Vec<u8>) -> Result<(), RDError> {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || write_atomic_blocking(&path, &data, false)).await
+        .map_err(|e| RDError::IO(std::io::Error::other(e)))?
+}
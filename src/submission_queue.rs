@@ -0,0 +1,283 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use getset::Getters;
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use tokio::sync::{broadcast, Notify};
+use tokio_util::sync::CancellationToken;
+use crate::{RDClient, RDTraitAsync};
+use crate::data_struct::RDError;
+use crate::data_struct::error::ApiErrorCode;
+use crate::data_struct::torrent::{ParamsTorrentHost, TorrentAdd};
+use crate::magnet::Magnet;
+use crate::state_file::{write_atomic, StateFile};
+use crate::torrent_meta::TorrentMeta;
+
+/// Active count is checked this often while torrents are pending.
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Torrent waiting to be added.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[allow(non_camel_case_types)]
+pub enum SubmissionSource {
+    MAGNET(String),
+    /// Path of a .torrent file, copied in storage of queue when pushed.
+    FILE(String),
+}
+
+impl From<Magnet> for SubmissionSource {
+    fn from(d: Magnet) -> Self {
+        SubmissionSource::MAGNET(d.to_string())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Getters)]
+pub struct Submission {
+    #[getset(get = "pub")]
+    id: u64,
+    #[getset(get = "pub")]
+    source: SubmissionSource,
+    /// Host of torrent, default host when None.
+    #[getset(get = "pub")]
+    host: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+#[allow(non_camel_case_types)]
+pub enum SubmissionEvent {
+    /// Position starts at 1 for next submission.
+    QUEUED { id: u64, position: usize },
+    /// Position changed after submissions before it left queue.
+    POSITION { id: u64, position: usize },
+    SUBMITTED { id: u64, torrent: TorrentAdd },
+    /// Real-Debrid refused torrent, it is removed from queue.
+    FAILED { id: u64, error: String },
+    /// No slot left, active torrents and limit.
+    WAITING { nb: u32, limit: u32 },
+}
+
+/// State saved on disk.
+#[derive(Serialize, Deserialize, Debug)]
+struct SubmissionState {
+    next_id: u64,
+    pending: Vec<Submission>,
+}
+
+impl Default for SubmissionState {
+    fn default() -> Self {
+        SubmissionState { next_id: 1, pending: Vec::new() }
+    }
+}
+
+#[derive(Debug)]
+struct Inner {
+    client: RDClient,
+    state: StateFile<SubmissionState>,
+    events: broadcast::Sender<SubmissionEvent>,
+    wake: Notify,
+    shutdown: CancellationToken,
+}
+
+/// Queue of torrents saved on disk, added by run only while active torrents are under limit.
+/// Clones share the same queue.
+#[derive(Debug, Clone)]
+pub struct TorrentSubmissionQueue {
+    inner: Arc<Inner>,
+}
+
+impl TorrentSubmissionQueue {
+
+    /// Open queue saved at path, or a new empty queue.
+    /// Files of .torrent are stored in a folder named like path with .torrents added.
+    pub async fn open(client: RDClient, path: impl AsRef<Path>) -> Result<TorrentSubmissionQueue, RDError> {
+        let (events, _) = broadcast::channel(256);
+        Ok(TorrentSubmissionQueue { inner: Arc::new(Inner {
+            client,
+            state: StateFile::open(path.as_ref()).await?,
+            events,
+            wake: Notify::new(),
+            shutdown: CancellationToken::new(),
+        }) })
+    }
+
+    /// Queue a torrent, return its id and position.
+    /// A .torrent file is checked and copied, so it can be removed once pushed.
+    pub async fn push(&self, source: impl Into<SubmissionSource>, host: Option<String>) -> Result<(u64, usize), RDError> {
+        let id = {
+            let mut state = self.state();
+            state.next_id += 1;
+            state.next_id - 1
+        };
+        let source = match source.into() {
+            SubmissionSource::FILE(d) => SubmissionSource::FILE(self.store(id, Path::new(&d)).await?),
+            source => source,
+        };
+        let position = {
+            let mut state = self.state();
+            state.pending.push(Submission { id, source, host });
+            state.pending.len()
+        };
+        self.emit(SubmissionEvent::QUEUED { id, position });
+        self.save().await?;
+        self.inner.wake.notify_one();
+        Ok((id, position))
+    }
+
+    /// Remove a pending torrent.
+    pub async fn remove(&self, id: u64) -> Result<(), RDError> {
+        {
+            let mut state = self.state();
+            let position = state.pending.iter().position(|d| d.id == id).ok_or(RDError::UNKNOWN_RESSOURCE)?;
+            self.remove_stored(&state.pending.remove(position));
+        }
+        self.emit_positions();
+        self.save().await
+    }
+
+    /// Position of a pending torrent, 1 for next submission.
+    pub fn position(&self, id: u64) -> Option<usize> {
+        self.state().pending.iter().position(|d| d.id == id).map(|d| d + 1)
+    }
+
+    pub fn pending(&self) -> Vec<Submission> {
+        self.state().pending.clone()
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<SubmissionEvent> {
+        self.inner.events.subscribe()
+    }
+
+    /// Submit torrents until shutdown is called.
+    /// Slots are checked again when a torrent is pushed, and every 30s while torrents are pending.
+    pub async fn run(&self) {
+        loop {
+            if self.inner.shutdown.is_cancelled() {
+                return;
+            }
+
+            let pending = !self.state().pending.is_empty();
+            if pending {
+                self.submit_pending().await;
+            }
+
+            tokio::select! {
+                _ = self.inner.wake.notified() => {},
+                _ = tokio::time::sleep(POLL_INTERVAL), if pending => {},
+                _ = self.inner.shutdown.cancelled() => {},
+            }
+        }
+    }
+
+    /// Stop run, pending torrents are kept for next run.
+    pub fn shutdown(&self) {
+        self.inner.shutdown.cancel();
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, SubmissionState> {
+        self.inner.state.lock()
+    }
+
+    fn emit(&self, event: SubmissionEvent) {
+        let _ = self.inner.events.send(event);
+    }
+
+    fn emit_positions(&self) {
+        let ids = self.state().pending.iter().map(|d| d.id).collect::<Vec<_>>();
+        for (index, id) in ids.into_iter().enumerate() {
+            self.emit(SubmissionEvent::POSITION { id, position: index + 1 });
+        }
+    }
+
+    async fn save(&self) -> Result<(), RDError> {
+        self.inner.state.save().await
+    }
+
+    /// Folder of stored .torrent files.
+    fn storage(&self) -> PathBuf {
+        let mut storage = self.inner.state.path().as_os_str().to_owned();
+        storage.push(".torrents");
+        PathBuf::from(storage)
+    }
+
+    /// Copy a valid .torrent file in storage, return path of copy.
+    async fn store(&self, id: u64, path: &Path) -> Result<String, RDError> {
+        let data = fs::read(path).await?;
+        TorrentMeta::from_bytes(&data)?;
+        let storage = self.storage();
+        fs::create_dir_all(&storage).await?;
+        let stored = storage.join(format!("{}.torrent", id));
+        write_atomic(&stored, data).await?;
+        Ok(stored.to_string_lossy().into_owned())
+    }
+
+    /// Delete stored copy of a submission which left queue.
+    fn remove_stored(&self, submission: &Submission) {
+        if let SubmissionSource::FILE(d) = &submission.source {
+            // Queues saved before files were copied point to files of user.
+            if Path::new(d).starts_with(self.storage()) {
+                let _ = std::fs::remove_file(d);
+            }
+        }
+    }
+
+    /// Submit pending torrents in order while slots are free.
+    async fn submit_pending(&self) {
+        let Ok(count) = self.inner.client.get_torrents_active_count().await else {
+            return;
+        };
+        let mut free = count.limit().saturating_sub(*count.nb());
+        let mut removed = false;
+
+        while free > 0 {
+            let Some(submission) = self.state().pending.first().cloned() else {
+                break;
+            };
+            let host = submission.host.clone().map(ParamsTorrentHost::FROM_HOST);
+            let result = match submission.source.clone() {
+                SubmissionSource::MAGNET(d) => self.inner.client.add_torrent_magnet(d, host).await,
+                SubmissionSource::FILE(d) => self.inner.client.add_torrent_file(d, host).await,
+            };
+
+            let event = match result {
+                Ok(torrent) => {
+                    free -= 1;
+                    SubmissionEvent::SUBMITTED { id: submission.id, torrent }
+                },
+                // Limit reached before active count was updated, try again later.
+                Err(e) if Self::is_full(&e) || e.is_transient() => {
+                    free = 0;
+                    break;
+                },
+                Err(e) => SubmissionEvent::FAILED { id: submission.id, error: e.to_string() },
+            };
+            self.state().pending.retain(|d| d.id != submission.id);
+            self.remove_stored(&submission);
+            removed = true;
+            self.emit(event);
+        }
+
+        if removed {
+            self.emit_positions();
+            let _ = self.save().await;
+        }
+        if free == 0 && !self.state().pending.is_empty() {
+            self.emit(SubmissionEvent::WAITING { nb: *count.nb(), limit: *count.limit() });
+        }
+    }
+
+    /// Error of Real-Debrid when active torrents are over limit.
+    fn is_full(e: &RDError) -> bool {
+        e.api_code() == Some(ApiErrorCode::TOO_MANY_ACTIVE_DOWNLOADS)
+    }
+
+}
+
+impl RDClient {
+
+    /// Open queue adding torrents within active torrents limit, saved at path.
+    pub async fn submission_queue(&self, path: impl AsRef<Path>) -> Result<TorrentSubmissionQueue, RDError> {
+        TorrentSubmissionQueue::open(self.clone(), path).await
+    }
+
+}
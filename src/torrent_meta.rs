@@ -0,0 +1,362 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::ops::Range;
+use getset::Getters;
+use sha1::{Digest, Sha1};
+use sha2::Sha256;
+use tokio::fs;
+use crate::data_struct::RDError;
+
+/// Deeper values are rejected, v2 file trees nest one level per folder.
+const MAX_DEPTH: usize = 256;
+/// Length of a v1 piece hash.
+const PIECE_HASH_LEN: usize = 20;
+/// Smallest piece length allowed by v2.
+const MIN_PIECE_LENGTH_V2: u64 = 16 * 1024;
+
+/// Bencoded value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum Bencode {
+    INT(i64),
+    BYTES(Vec<u8>),
+    LIST(Vec<Bencode>),
+    DICT(BTreeMap<Vec<u8>, Bencode>),
+}
+
+impl Bencode {
+
+    /// Parse one value which must use all data.
+    pub fn parse(data: &[u8]) -> Result<Bencode, RDError> {
+        let mut parser = Parser { data, position: 0, info: None };
+        let value = parser.value(0)?;
+        if parser.position != data.len() {
+            return Err(RDError::INVALID_TORRENT(format!("trailing data at byte {}", parser.position)));
+        }
+        Ok(value)
+    }
+
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            Bencode::INT(d) => Some(*d),
+            _ => None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Bencode::BYTES(d) => Some(d),
+            _ => None,
+        }
+    }
+
+    /// Bytes as utf-8 string.
+    pub fn as_str(&self) -> Option<&str> {
+        std::str::from_utf8(self.as_bytes()?).ok()
+    }
+
+    /// Bytes as string, invalid utf-8 of older torrents replaced.
+    pub fn as_str_lossy(&self) -> Option<Cow<'_, str>> {
+        self.as_bytes().map(String::from_utf8_lossy)
+    }
+
+    pub fn as_list(&self) -> Option<&[Bencode]> {
+        match self {
+            Bencode::LIST(d) => Some(d),
+            _ => None,
+        }
+    }
+
+    pub fn as_dict(&self) -> Option<&BTreeMap<Vec<u8>, Bencode>> {
+        match self {
+            Bencode::DICT(d) => Some(d),
+            _ => None,
+        }
+    }
+
+    /// Value of key when self is a dictionary.
+    pub fn get(&self, key: &str) -> Option<&Bencode> {
+        self.as_dict()?.get(key.as_bytes())
+    }
+
+}
+
+struct Parser<'a> {
+    data: &'a [u8],
+    position: usize,
+    /// Raw bytes of info dictionary of root dictionary.
+    info: Option<Range<usize>>,
+}
+
+impl Parser<'_> {
+
+    fn error(&self, message: &str) -> RDError {
+        RDError::INVALID_TORRENT(format!("{} at byte {}", message, self.position))
+    }
+
+    fn peek(&self) -> Result<u8, RDError> {
+        self.data.get(self.position).copied().ok_or_else(|| self.error("unexpected end"))
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Bencode, RDError> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("nesting too deep"));
+        }
+        match self.peek()? {
+            b'i' => {
+                self.position += 1;
+                let digits = self.until(b'e')?;
+                let valid = match digits.strip_prefix(b"-") {
+                    Some(d) => !d.is_empty() && d[0] != b'0',
+                    None => !digits.is_empty() && (digits == b"0" || digits[0] != b'0'),
+                };
+                let int = std::str::from_utf8(digits).ok().filter(|_| valid).and_then(|d| d.parse::<i64>().ok());
+                int.map(Bencode::INT).ok_or_else(|| self.error("invalid integer"))
+            },
+            b'l' => {
+                self.position += 1;
+                let mut list = Vec::new();
+                while self.peek()? != b'e' {
+                    list.push(self.value(depth + 1)?);
+                }
+                self.position += 1;
+                Ok(Bencode::LIST(list))
+            },
+            b'd' => {
+                self.position += 1;
+                let mut dict = BTreeMap::new();
+                while self.peek()? != b'e' {
+                    let key = self.bytes()?;
+                    let start = self.position;
+                    let value = self.value(depth + 1)?;
+                    if depth == 0 && key == b"info" {
+                        self.info = Some(start..self.position);
+                    }
+                    if dict.insert(key, value).is_some() {
+                        return Err(self.error("duplicate key"));
+                    }
+                }
+                self.position += 1;
+                Ok(Bencode::DICT(dict))
+            },
+            b'0'..=b'9' => Ok(Bencode::BYTES(self.bytes()?)),
+            _ => Err(self.error("invalid value")),
+        }
+    }
+
+    fn bytes(&mut self) -> Result<Vec<u8>, RDError> {
+        let digits = self.until(b':')?;
+        let length = std::str::from_utf8(digits).ok()
+            .filter(|d| !d.is_empty() && (*d == "0" || !d.starts_with('0')))
+            .and_then(|d| d.parse::<usize>().ok())
+            .ok_or_else(|| self.error("invalid string length"))?;
+        let end = self.position.checked_add(length).filter(|end| *end <= self.data.len()).ok_or_else(|| self.error("string exceed data"))?;
+        let bytes = self.data[self.position..end].to_vec();
+        self.position = end;
+        Ok(bytes)
+    }
+
+    /// Bytes before delimiter, position after delimiter.
+    fn until(&mut self, delimiter: u8) -> Result<&[u8], RDError> {
+        let start = self.position;
+        let length = self.data[start..].iter().position(|d| *d == delimiter).ok_or_else(|| self.error("unexpected end"))?;
+        self.position = start + length + 1;
+        Ok(&self.data[start..start + length])
+    }
+
+}
+
+/// File contained in a torrent.
+#[derive(Debug, Clone, PartialEq, Eq, Getters)]
+pub struct TorrentMetaFile {
+    /// Path inside torrent starting with "/", like TorrentFile.
+    #[getset(get = "pub")]
+    path: String,
+    #[getset(get = "pub")]
+    bytes: u64,
+}
+
+/// Metadata of a .torrent file, v1, v2 or hybrid.
+#[derive(Debug, Clone, Getters)]
+pub struct TorrentMeta {
+    #[getset(get = "pub")]
+    name: String,
+    /// Lowercase hex like Torrent::hash, SHA-1 of info for v1 and hybrid, truncated SHA-256 for v2 only.
+    #[getset(get = "pub")]
+    info_hash: String,
+    /// Full SHA-256 of info in lowercase hex, when v2 or hybrid.
+    #[getset(get = "pub")]
+    info_hash_v2: Option<String>,
+    #[getset(get = "pub")]
+    files: Vec<TorrentMetaFile>,
+    /// Bytes of all files without padding.
+    #[getset(get = "pub")]
+    total_size: u64,
+    #[getset(get = "pub")]
+    piece_length: u64,
+    /// Trackers of announce and announce-list, without duplicates.
+    #[getset(get = "pub")]
+    trackers: Vec<String>,
+    #[getset(get = "pub")]
+    private: bool,
+}
+
+impl TorrentMeta {
+
+    /// Read and parse a .torrent file.
+    pub async fn from_path(path: &str) -> Result<TorrentMeta, RDError> {
+        if !fs::try_exists(path).await? {
+            return Err(RDError::PATH_NOT_RIGHT);
+        }
+        TorrentMeta::from_bytes(&fs::read(path).await?)
+    }
+
+    /// Parse and validate a .torrent file.
+    pub fn from_bytes(data: &[u8]) -> Result<TorrentMeta, RDError> {
+        let mut parser = Parser { data, position: 0, info: None };
+        let root = parser.value(0)?;
+        if parser.position != data.len() {
+            return Err(parser.error("trailing data"));
+        }
+        if root.as_dict().is_none() {
+            return Err(invalid("root is not a dictionary"));
+        }
+        let (Some(raw_info), Some(info)) = (parser.info.map(|range| &data[range]), root.get("info")) else {
+            return Err(invalid("missing info dictionary"));
+        };
+        if info.as_dict().is_none() {
+            return Err(invalid("info is not a dictionary"));
+        }
+
+        let name = info.get("name.utf-8").or(info.get("name")).and_then(Bencode::as_str_lossy)
+            .ok_or_else(|| invalid("missing name"))?
+            .into_owned();
+        let piece_length = info.get("piece length").and_then(Bencode::as_int).filter(|d| *d > 0)
+            .ok_or_else(|| invalid("missing piece length"))? as u64;
+
+        let v1 = info.get("pieces").is_some();
+        let v2 = info.get("meta version").and_then(Bencode::as_int) == Some(2);
+        if !v1 && !v2 {
+            return Err(invalid("neither pieces nor meta version 2"));
+        }
+
+        let files = if v2 {
+            if piece_length < MIN_PIECE_LENGTH_V2 || !piece_length.is_power_of_two() {
+                return Err(invalid("piece length of v2 must be a power of two of at least 16 KiB"));
+            }
+            let tree = info.get("file tree").filter(|d| d.as_dict().is_some()).ok_or_else(|| invalid("missing file tree"))?;
+            let mut files = Vec::new();
+            file_tree(tree, String::new(), &mut files)?;
+            files
+        }
+        else {
+            Vec::new()
+        };
+
+        let files = if v1 {
+            let (v1_files, size) = v1_files(info, &name)?;
+            let pieces = info.get("pieces").and_then(Bencode::as_bytes).ok_or_else(|| invalid("pieces is not a string"))?;
+            if pieces.len() % PIECE_HASH_LEN != 0 {
+                return Err(invalid("pieces length is not a multiple of 20"));
+            }
+            if (pieces.len() / PIECE_HASH_LEN) as u64 != size.div_ceil(piece_length) {
+                return Err(invalid("pieces count does not match size"));
+            }
+            v1_files
+        }
+        else {
+            files
+        };
+        if files.is_empty() {
+            return Err(invalid("no file"));
+        }
+
+        let info_hash_v2 = v2.then(|| hex(&Sha256::digest(raw_info)));
+        let info_hash = match &info_hash_v2 {
+            Some(d) if !v1 => d[..PIECE_HASH_LEN * 2].to_string(),
+            _ => hex(&Sha1::digest(raw_info)),
+        };
+
+        let mut trackers: Vec<String> = Vec::new();
+        let announce_list = root.get("announce-list").and_then(Bencode::as_list).unwrap_or_default();
+        let tiers = root.get("announce").into_iter().chain(announce_list.iter().flat_map(|tier| tier.as_list().unwrap_or_default()));
+        for tracker in tiers.filter_map(Bencode::as_str) {
+            if !trackers.iter().any(|d| d == tracker) {
+                trackers.push(tracker.to_string());
+            }
+        }
+
+        Ok(TorrentMeta {
+            name,
+            info_hash,
+            info_hash_v2,
+            total_size: files.iter().map(|d| d.bytes).sum(),
+            files,
+            piece_length,
+            trackers,
+            private: info.get("private").and_then(Bencode::as_int) == Some(1),
+        })
+    }
+
+}
+
+fn invalid(message: &str) -> RDError {
+    RDError::INVALID_TORRENT(message.to_string())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|d| format!("{:02x}", d)).collect()
+}
+
+/// Files without padding and size with padding of a v1 info.
+fn v1_files(info: &Bencode, name: &str) -> Result<(Vec<TorrentMetaFile>, u64), RDError> {
+    let length = |file: &Bencode| file.get("length").and_then(Bencode::as_int).filter(|d| *d >= 0).map(|d| d as u64);
+
+    let Some(list) = info.get("files") else {
+        let bytes = length(info).ok_or_else(|| invalid("missing length"))?;
+        return Ok((vec![TorrentMetaFile { path: format!("/{}", name), bytes }], bytes));
+    };
+
+    let list = list.as_list().ok_or_else(|| invalid("files is not a list"))?;
+    let mut files = Vec::with_capacity(list.len());
+    let mut size = 0u64;
+    for file in list {
+        let bytes = length(file).ok_or_else(|| invalid("missing file length"))?;
+        size = size.checked_add(bytes).ok_or_else(|| invalid("size overflow"))?;
+        if file.get("attr").and_then(Bencode::as_bytes).is_some_and(|attr| attr.contains(&b'p')) {
+            continue;
+        }
+        let parts = file.get("path.utf-8").or(file.get("path")).and_then(Bencode::as_list).unwrap_or_default();
+        let parts = parts.iter().map(|part| part.as_str_lossy().filter(|d| valid_part(d))).collect::<Option<Vec<_>>>();
+        match parts {
+            Some(parts) if !parts.is_empty() => files.push(TorrentMetaFile { path: format!("/{}", parts.join("/")), bytes }),
+            _ => return Err(invalid("invalid file path")),
+        }
+    }
+    Ok((files, size))
+}
+
+/// Add files of a v2 file tree, leaves are under an empty key.
+fn file_tree(tree: &Bencode, path: String, files: &mut Vec<TorrentMetaFile>) -> Result<(), RDError> {
+    let dict = tree.as_dict().ok_or_else(|| invalid("file tree entry is not a dictionary"))?;
+    for (key, entry) in dict {
+        if key.is_empty() {
+            if path.is_empty() {
+                return Err(invalid("file without name in file tree"));
+            }
+            let bytes = entry.get("length").and_then(Bencode::as_int).filter(|d| *d >= 0).ok_or_else(|| invalid("missing file length"))?;
+            if bytes > 0 && entry.get("pieces root").and_then(Bencode::as_bytes).is_none_or(|d| d.len() != 32) {
+                return Err(invalid("missing pieces root"));
+            }
+            files.push(TorrentMetaFile { path: path.clone(), bytes: bytes as u64 });
+            continue;
+        }
+        let part = Some(String::from_utf8_lossy(key)).filter(|d| valid_part(d)).ok_or_else(|| invalid("invalid file path"))?;
+        file_tree(entry, format!("{}/{}", path, part), files)?;
+    }
+    Ok(())
+}
+
+fn valid_part(part: &str) -> bool {
+    !part.is_empty() && part != "." && part != ".." && !part.contains('/')
+}
@@ -0,0 +1,254 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+use futures::stream::{self, Stream, StreamExt};
+use tokio::time::Instant;
+use crate::{RDClient, RDTraitAsync};
+use crate::data_struct::RDError;
+use crate::data_struct::torrent::{ParamsTorrent, Torrent, TorrentStatus};
+
+pub const DEFAULT_MIN_INTERVAL: Duration = Duration::from_secs(2);
+pub const DEFAULT_MAX_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Change of a watched torrent.
+#[derive(Debug, Clone)]
+#[allow(non_camel_case_types)]
+pub enum TorrentEvent {
+    /// First status seen has no old status.
+    STATUS_CHANGED { id: String, old: Option<TorrentStatus>, new: TorrentStatus },
+    /// Progress from 0 to 100.
+    PROGRESS { id: String, progress: u16 },
+    /// Only present while downloading, compressing or uploading (speed) and downloading or converting magnet (seeders).
+    STATS { id: String, speed: Option<u32>, seeders: Option<u32> },
+    FINISHED(Torrent),
+    FAILED(Torrent),
+}
+
+/// Poll torrents until they are downloaded or failed.
+/// Torrents are polled more often after a status change and less when nothing change.
+#[derive(Debug, Clone)]
+pub struct TorrentWatcher {
+    client: RDClient,
+    ids: Vec<String>,
+    min_interval: Duration,
+    max_interval: Duration,
+}
+
+#[derive(Debug)]
+struct Watched {
+    id: String,
+    last: Option<Torrent>,
+    interval: Duration,
+    next_poll: Instant,
+    done: bool,
+}
+
+#[derive(Debug)]
+struct WatchState {
+    client: RDClient,
+    min_interval: Duration,
+    max_interval: Duration,
+    torrents: Vec<Watched>,
+    events: VecDeque<Result<TorrentEvent, RDError>>,
+}
+
+impl TorrentWatcher {
+
+    pub fn new(client: RDClient) -> TorrentWatcher {
+        TorrentWatcher { client, ids: Vec::new(), min_interval: DEFAULT_MIN_INTERVAL, max_interval: DEFAULT_MAX_INTERVAL }
+    }
+
+    /// Add a torrent to watch.
+    pub fn watch(mut self, torrent: ParamsTorrent) -> Self {
+        self.ids.push(match torrent {
+            ParamsTorrent::FROM_STRUCT(d) => d.id().to_string(),
+            ParamsTorrent::FROM_ADD(d) => d.id().to_string(),
+            ParamsTorrent::FROM_ID(d) => d,
+        });
+        self
+    }
+
+    /// Interval after a status change (default : 2s).
+    pub fn min_interval(mut self, interval: Duration) -> Self {
+        self.min_interval = interval;
+        self
+    }
+
+    /// Interval reached when nothing change (default : 60s).
+    pub fn max_interval(mut self, interval: Duration) -> Self {
+        self.max_interval = interval;
+        self
+    }
+
+    /// Stream of events, ending when all torrents are finished or failed.
+    /// Transient errors are sent and polling continue, other errors stop watching the torrent.
+    pub fn events(self) -> impl Stream<Item = Result<TorrentEvent, RDError>> + Send + 'static {
+        let now = Instant::now();
+        let state = WatchState {
+            client: self.client,
+            min_interval: self.min_interval,
+            max_interval: self.max_interval.max(self.min_interval),
+            torrents: self.ids.into_iter().map(|id| Watched { id, last: None, interval: self.min_interval, next_poll: now, done: false }).collect(),
+            events: VecDeque::new(),
+        };
+
+        stream::unfold(state, |mut state| async move {
+            let event = state.next().await?;
+            Some((event, state))
+        })
+    }
+
+    /// Wait until all torrents are finished or failed, return them in order of watch.
+    /// Stop at first error which is not transient.
+    pub async fn wait(self) -> Result<Vec<Torrent>, RDError> {
+        let ids = self.ids.clone();
+        let mut ended = Vec::with_capacity(ids.len());
+        let mut events = Box::pin(self.events());
+        while let Some(event) = events.next().await {
+            match event {
+                Ok(TorrentEvent::FINISHED(torrent)) | Ok(TorrentEvent::FAILED(torrent)) => ended.push(torrent),
+                Ok(_) => {},
+                Err(e) if e.is_transient() => {},
+                Err(e) => return Err(e),
+            }
+        }
+        ended.sort_by_key(|torrent| ids.iter().position(|id| id == torrent.id()));
+        Ok(ended)
+    }
+
+}
+
+impl WatchState {
+
+    async fn next(&mut self) -> Option<Result<TorrentEvent, RDError>> {
+        loop {
+            if let Some(event) = self.events.pop_front() {
+                return Some(event);
+            }
+
+            let watched = self.torrents.iter_mut().filter(|watched| !watched.done).min_by_key(|watched| watched.next_poll)?;
+            tokio::time::sleep_until(watched.next_poll).await;
+
+            match self.client.get_torrents_info(ParamsTorrent::FROM_ID(watched.id.clone())).await {
+                Ok(torrent) => {
+                    let changed = Self::compare(watched, torrent, &mut self.events);
+                    watched.interval = if changed {
+                        self.min_interval
+                    }
+                    else {
+                        watched.interval.saturating_mul(2).min(self.max_interval)
+                    };
+                },
+                Err(e) if e.is_transient() => {
+                    watched.interval = watched.interval.saturating_mul(2).min(self.max_interval);
+                    self.events.push_back(Err(e));
+                },
+                Err(e) => {
+                    watched.done = true;
+                    self.events.push_back(Err(e));
+                },
+            }
+            watched.next_poll = Instant::now() + watched.interval;
+        }
+    }
+
+    /// Push events of differences with last poll, true if status changed.
+    fn compare(watched: &mut Watched, torrent: Torrent, events: &mut VecDeque<Result<TorrentEvent, RDError>>) -> bool {
+        let id = watched.id.clone();
+        let status = torrent.torrent_status();
+        let old = watched.last.as_ref();
+
+        let old_status = old.map(|d| d.torrent_status());
+        let changed = old_status.as_ref() != Some(&status);
+        if changed {
+            events.push_back(Ok(TorrentEvent::STATUS_CHANGED { id: id.clone(), old: old_status, new: status.clone() }));
+        }
+        if old.map(|d| *d.progress()) != Some(*torrent.progress()) {
+            events.push_back(Ok(TorrentEvent::PROGRESS { id: id.clone(), progress: *torrent.progress() }));
+        }
+        let stats = (*torrent.speed(), *torrent.seeders());
+        if old.map(|d| (*d.speed(), *d.seeders())) != Some(stats) && stats != (None, None) {
+            events.push_back(Ok(TorrentEvent::STATS { id, speed: stats.0, seeders: stats.1 }));
+        }
+
+        if status.is_finished() {
+            watched.done = true;
+            events.push_back(Ok(TorrentEvent::FINISHED(torrent.clone())));
+        }
+        else if status.is_failed() {
+            watched.done = true;
+            events.push_back(Ok(TorrentEvent::FAILED(torrent.clone())));
+        }
+        watched.last = Some(torrent);
+        changed
+    }
+
+}
+
+impl RDClient {
+
+    /// Create watcher polling torrents.
+    pub fn torrent_watcher(&self) -> TorrentWatcher {
+        TorrentWatcher::new(self.clone())
+    }
+
+    /// Wait until torrent is downloaded or failed.
+    pub async fn wait_torrent(&self, torrent: ParamsTorrent) -> Result<Torrent, RDError> {
+        self.torrent_watcher().watch(torrent).wait().await?.pop().ok_or(RDError::UNKNOWN_RESSOURCE)
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn torrent(status: &str, progress: u16, speed: Option<u32>) -> Torrent {
+        let mut value = serde_json::to_value(Torrent::default()).unwrap();
+        value["id"] = "ID".into();
+        value["status"] = status.into();
+        value["progress"] = progress.into();
+        value["speed"] = speed.into();
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn compare(watched: &mut Watched, torrent: Torrent) -> (bool, Vec<TorrentEvent>) {
+        let mut events = VecDeque::new();
+        let changed = WatchState::compare(watched, torrent, &mut events);
+        (changed, events.into_iter().map(Result::unwrap).collect())
+    }
+
+    #[test]
+    fn watcher_compare() {
+        let mut watched = Watched { id: "ID".to_string(), last: None, interval: DEFAULT_MIN_INTERVAL, next_poll: Instant::now(), done: false };
+
+        let (changed, events) = compare(&mut watched, torrent("downloading", 10, Some(100)));
+        assert!(changed);
+        assert!(matches!(events.as_slice(), [
+            TorrentEvent::STATUS_CHANGED { old: None, new: TorrentStatus::DOWNLOADING, .. },
+            TorrentEvent::PROGRESS { progress: 10, .. },
+            TorrentEvent::STATS { speed: Some(100), seeders: None, .. },
+        ]));
+
+        let (changed, events) = compare(&mut watched, torrent("downloading", 10, Some(100)));
+        assert!(!changed && events.is_empty());
+
+        // Progress alone is not a status change, stats gone are not sent.
+        let (changed, events) = compare(&mut watched, torrent("downloading", 50, None));
+        assert!(!changed);
+        assert!(matches!(events.as_slice(), [TorrentEvent::PROGRESS { progress: 50, .. }]));
+
+        let (changed, events) = compare(&mut watched, torrent("downloaded", 100, None));
+        assert!(changed && watched.done);
+        assert!(matches!(events.as_slice(), [
+            TorrentEvent::STATUS_CHANGED { old: Some(TorrentStatus::DOWNLOADING), new: TorrentStatus::DOWNLOADED, .. },
+            TorrentEvent::PROGRESS { progress: 100, .. },
+            TorrentEvent::FINISHED(_),
+        ]));
+
+        let mut watched = Watched { id: "ID".to_string(), last: Some(torrent("magnet_conversion", 0, None)), interval: DEFAULT_MIN_INTERVAL, next_poll: Instant::now(), done: false };
+        let (changed, events) = compare(&mut watched, torrent("dead", 0, None));
+        assert!(changed && watched.done);
+        assert!(matches!(events.as_slice(), [TorrentEvent::STATUS_CHANGED { new: TorrentStatus::DEAD, .. }, TorrentEvent::FAILED(_)]));
+    }
+
+}
@@ -0,0 +1,60 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tokio::time::Instant;
+use crate::data_struct::RDError;
+
+/// Value with time of fetch.
+pub(crate) type Cached<T> = Option<(T, Instant)>;
+
+/// Value fetched on first use and again after ttl.
+/// Clones share the same value.
+#[derive(Debug)]
+pub(crate) struct TtlCache<T> {
+    value: Arc<RwLock<Cached<T>>>,
+}
+
+impl<T> Clone for TtlCache<T> {
+    fn clone(&self) -> Self {
+        TtlCache { value: self.value.clone() }
+    }
+}
+
+impl<T: Clone> TtlCache<T> {
+
+    pub(crate) fn new() -> TtlCache<T> {
+        TtlCache { value: Arc::new(RwLock::new(None)) }
+    }
+
+    /// Cached value, fetched if missing or older than ttl.
+    pub(crate) async fn get<F: Future<Output = Result<T, RDError>>>(&self, ttl: Duration, fetch: impl FnOnce() -> F) -> Result<T, RDError> {
+        if let Some((value, loaded)) = self.value.read().await.as_ref() {
+            if loaded.elapsed() < ttl {
+                return Ok(value.clone());
+            }
+        }
+
+        let mut cached = self.value.write().await;
+        // Another task may have refreshed while waiting lock.
+        if let Some((value, loaded)) = cached.as_ref() {
+            if loaded.elapsed() < ttl {
+                return Ok(value.clone());
+            }
+        }
+
+        let value = fetch().await?;
+        *cached = Some((value.clone(), Instant::now()));
+        Ok(value)
+    }
+
+    pub(crate) async fn set(&self, value: T) {
+        *self.value.write().await = Some((value, Instant::now()));
+    }
+
+    /// Change cached value in place.
+    pub(crate) async fn update(&self, update: impl FnOnce(&mut Cached<T>)) {
+        update(&mut *self.value.write().await);
+    }
+
+}